@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use struct_baker::interpolation::{Interpolate, IntoInterpolation, RuntimeInterpolationError};
+use struct_baker::interpolation::{HoleSyntax, Interpolate, IntoInterpolation, RuntimeInterpolationError, RuntimeParsed};
 use struct_baker::{bake, interpolation::{Interpolatable, FlattenInterpolation}, Bake, Bakeable};
 use nom::branch::alt;
 use nom::character::complete::{alphanumeric0, alphanumeric1, char, digit1};
@@ -9,7 +9,6 @@ use nom::multi::{many0, separated_list0};
 use nom::sequence::delimited;
 use nom::IResult;
 
-use parse_hyperlinks::take_until_unbalanced;
 
 #[derive(Bake, Debug, PartialEq)]
 #[bake(to_tokens)]
@@ -17,7 +16,7 @@ pub enum Json {
     Number(i64),
     Boolean(bool),
     String(String),
-    #[interpolate]
+    #[interpolate(elements)]
     List(Vec<Json>),
     #[interpolate]
     Dict(HashMap<String, Json>),
@@ -30,7 +29,7 @@ impl Json {
             Json::Boolean(b) => b.to_string(),
             Json::String(s) => format!("{:?}", s.to_string()),
             Json::List(list) => {
-                let inner: Vec<_> = list.iter().map(Json::as_json).collect();
+                let inner: Vec<_> = list.iter().map(|x| x.as_json()).collect();
                 format!("[{}]", inner.join(", ")).to_owned()
             }
             Json::Dict(dict) => {
@@ -95,13 +94,15 @@ pub fn parse(input: &str) -> Result<Json, JsonError> {
 
 pub fn parse_json_node(i: &str) -> IResult<&str, Interpolatable<Json>> {
     let (i, _) = whitespace(i)?;
-    alt((
+    let (i, node) = alt((
         parse_interpolation,
         parse_dict,
         parse_list,
         parse_number,
         parse_string,
-    ))(i)
+    ))(i)?;
+    let (i, _) = whitespace(i)?;
+    Ok((i, node))
 }
 
 fn parse_number(i: &str) -> IResult<&str, Interpolatable<Json>> {
@@ -119,14 +120,22 @@ fn parse_string(i: &str) -> IResult<&str, Interpolatable<Json>> {
 }
 
 fn parse_interpolation(i: &str) -> IResult<&str, Interpolatable<Json>> {
-    let (i, _) = char('$')(i)?;
-    let (i, _) = whitespace(i)?;
-    let (i, tree) = delimited(char('{'), take_until_unbalanced('{', '}'), char('}'))(i)?;
+    let (i, tree) = HoleSyntax::Dollar
+        .scan(i)
+        .map_err(|_| nom::Err::Error(nom::error::Error::new(i, nom::error::ErrorKind::Char)))?;
     let (i, _) = whitespace(i)?;
 
-    let tree = struct_baker::util::parse_str(tree).expect("syntax error");
+    // `${str: ...}` is parsed at runtime by `lib::parse`
+    let hole = match tree.split_once(':') {
+        Some((prefix, input)) if prefix.trim() == "str" && !input.starts_with(':') => {
+            RuntimeParsed::parse(struct_baker::util::parse_quote!(lib::parse), input)
+                .expect("syntax error")
+                .into()
+        }
+        _ => Interpolatable::parse_hole(tree).expect("syntax error"),
+    };
 
-    Ok((i, Interpolatable::Inter(tree)))
+    Ok((i, hole))
 }
 
 fn parse_list(i: &str) -> IResult<&str, Interpolatable<Json>> {
@@ -136,7 +145,7 @@ fn parse_list(i: &str) -> IResult<&str, Interpolatable<Json>> {
         char(']'),
     )(i)?;
 
-    Ok((i, Json::List(list.flatten_interpolation().nom(i)?).interpolate()))
+    Ok((i, Json::List(list.nom(i)?).interpolate()))
 }
 
 fn parse_dict(i: &str) -> IResult<&str, Interpolatable<Json>> {
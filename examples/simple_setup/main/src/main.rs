@@ -25,4 +25,19 @@ fn main() {
     };
 
     println!("{}", node.as_json());
+
+    let two = 2;
+    let list = json!([1, ${two}, 3]);
+
+    println!("{}", list.as_json());
+
+    let user_input = String::from("[1, 2, 3]");
+
+    let parsed = json! {
+        {
+            "parsed": ${str: user_input}
+        }
+    };
+
+    println!("{}", parsed.as_json());
 }
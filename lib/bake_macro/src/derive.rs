@@ -1,21 +1,19 @@
 use proc_macro2::{Ident, Span};
 use quote::{quote, ToTokens};
-use syn::{DeriveInput, Visibility, Data, DataStruct, Fields, DataEnum, NestedMeta, Meta, Attribute, AttributeArgs, Path, Field, Type, TypePath};
+use syn::{DeriveInput, Generics, Visibility, Data, DataStruct, Fields, DataEnum, NestedMeta, Meta, Attribute, AttributeArgs, Path, Field, Type, TypePath};
 
-use crate::find_arg;
+use crate::{
+    find_arg, find_path_arg, parse_args,
+    interpolation::{field_modes, Interpolation},
+};
 
 
-fn get_attrs(attrs: &[Attribute], name: &str) -> AttributeArgs {
+pub(crate) fn get_attrs(attrs: &[Attribute], name: &str) -> AttributeArgs {
     
     attrs.iter()
         .find(|x| x.path.is_ident(name))
-        .and_then(|x| x.parse_meta().ok())
-        .and_then(|x| match x {
-            Meta::List(x) => Some(x),
-            _ => None,
-        })
-        
-        .map(|x| x.nested.into_iter().collect())
+        .and_then(|x| x.parse_args::<proc_macro2::TokenStream>().ok())
+        .and_then(|x| parse_args(x).ok())
         .unwrap_or_default()
 
 }
@@ -34,13 +32,15 @@ pub fn generate_impl(derive_input: DeriveInput) -> proc_macro2::TokenStream {
     let args = get_attrs(&attrs, "bake");
 
     
-    let alias = if let Some(NestedMeta::Meta(Meta::List(alias))) = find_arg(&args, "bake_as") {
-        match alias.nested.first().cloned() {
-            Some(NestedMeta::Meta(Meta::Path(x))) => Some(x),
-            _ => None,
-        }
-    } else {
-        None
+    let alias = find_path_arg(&args, "bake_as");
+    let interpolate_all = match find_arg(&args, "interpolate") {
+        Some(_) => Interpolation::Whole,
+        None => Interpolation::None,
+    };
+    // Template twins keep the original type free of holes, only the twin bakes elements
+    let modes = match find_arg(&args, "template") {
+        Some(_) => Vec::new(),
+        None => field_modes(&mut data.clone(), interpolate_all),
     };
     
 
@@ -48,32 +48,72 @@ pub fn generate_impl(derive_input: DeriveInput) -> proc_macro2::TokenStream {
         panic!("Can only be used on public Types")
     }
 
-    let imp = match data {
-        Data::Struct(x) => inplace_struct(&ident, alias, x),
-        Data::Enum(x) => inplace_enum(&ident, alias, x),
+    let elements = ElementFields { modes: &modes, macro_only: true };
+    bake_impl(&ident, &ident, &generics, data, alias, elements)
+}
+
+/// Which fields are baked with `BakeElements`, see `#[interpolate(elements)]`
+#[derive(Clone, Copy)]
+pub(crate) struct ElementFields<'a> {
+    /// The mode of every field, grouped by variant like `field_modes` returns them
+    pub modes: &'a [Vec<Interpolation>],
+    /// Only bake the elements with the 'macro' feature, the fields are plain types without it
+    pub macro_only: bool,
+}
+
+impl ElementFields<'_> {
+    fn of(&self, variant: usize) -> &[Interpolation] {
+        self.modes.get(variant).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Implements `Bake` for `ident` so it is baked as an instance of `baked`
+///
+/// Both types need the same fields, this is used for the twin types created with `#[bake(template = ..)]`.
+pub(crate) fn bake_impl(ident: &Ident, baked: &Ident, generics: &Generics, data: Data, alias: Option<Path>, elements: ElementFields) -> proc_macro2::TokenStream {
+    let (imp, baker) = match data {
+        Data::Struct(x) => (inplace_struct(baked, alias.clone(), &x, elements), baker_struct(baked, alias, &x, elements)),
+        Data::Enum(x) => (inplace_enum(baked, alias.clone(), &x, elements), baker_enum(baked, alias, &x, elements)),
         Data::Union(_) => todo!(),
     };
-
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     quote! {
-        impl #generics struct_baker::Bake for #ident #generics {
+        impl #impl_generics struct_baker::Bake for #ident #ty_generics #where_clause {
             fn to_stream(&self) -> struct_baker::util::TokenStream {
+                self.to_stream_with(&struct_baker::BakeContext::default())
+            }
+
+            fn try_to_stream(&self) -> Result<struct_baker::util::TokenStream, struct_baker::BakeError> {
+                self.try_to_stream_with(&struct_baker::BakeContext::default())
+            }
+
+            fn to_stream_with(&self, context: &struct_baker::BakeContext) -> struct_baker::util::TokenStream {
+                self.try_to_stream_with(context).unwrap_or_else(|e| e.to_compile_error())
+            }
+
+            fn try_to_stream_with(&self, context: &struct_baker::BakeContext) -> Result<struct_baker::util::TokenStream, struct_baker::BakeError> {
                 let object = self;
-                #imp.into()
+                Ok({ #imp }.into())
+            }
+
+            fn bake_into<Bk: struct_baker::Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, struct_baker::BakeError> {
+                let object = self;
+                Ok({ #baker })
             }
         }
     }
 }
 
-fn inplace_struct(ident: &Ident, alias: Option<Path>, data: DataStruct) -> proc_macro2::TokenStream {
+fn inplace_struct(ident: &Ident, alias: Option<Path>, data: &DataStruct, elements: ElementFields) -> proc_macro2::TokenStream {
     let destructured = destructure(&data.fields);
-    let conversion = convert(&data.fields);
+    let conversion = convert(&data.fields, None, false, elements.of(0), elements.macro_only);
     let restructured = restructure(&data.fields);
 
     match alias {
         Some(alias) => {
             quote!(
-                let #ident #destructured = object;
+                let Self #destructured = object;
 
                 #conversion
                 struct_baker::util::quote!(
@@ -85,8 +125,8 @@ fn inplace_struct(ident: &Ident, alias: Option<Path>, data: DataStruct) -> proc_
             let path = Ident::new("__path", Span::call_site());
 
             quote!(
-                let #ident #destructured = object;
-                let #path: struct_baker::util::Path = struct_baker::util::parse_str(module_path!()).unwrap();
+                let Self #destructured = object;
+                let #path = context.module_path(module_path!())?;
 
                 #conversion
                 struct_baker::util::quote!(
@@ -110,7 +150,7 @@ fn inplace_struct(ident: &Ident, alias: Option<Path>, data: DataStruct) -> proc_
 ///     b
 /// }
 /// ```
-fn destructure(fields: &Fields) -> proc_macro2::TokenStream {
+pub(crate) fn destructure(fields: &Fields) -> proc_macro2::TokenStream {
     let idents = determine_idents(fields);
 
     match fields {
@@ -120,12 +160,31 @@ fn destructure(fields: &Fields) -> proc_macro2::TokenStream {
     }
 }
 
-fn convert(fields: &Fields) -> proc_macro2::TokenStream {
+/// Bakes every field, errors are marked with the field and the variant if there is one
+///
+/// With `into_baker` the fields are baked into the outputs of `baker` instead of tokens.
+fn convert(fields: &Fields, variant: Option<&Ident>, into_baker: bool, modes: &[Interpolation], macro_only: bool) -> proc_macro2::TokenStream {
     let conversions = match fields {
         Fields::Named(fields) => fields.named.iter().collect(),
         Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
         Fields::Unit => Vec::new(),
-    }.into_iter().enumerate().map(field_conversion);
+    }.into_iter().enumerate().map(|(index, field)| {
+        let elements = modes.get(index) == Some(&Interpolation::Elements);
+        let conversion = field_conversion((index, field), variant, into_baker, false);
+        match (elements, macro_only) {
+            (false, _) => conversion,
+            (true, false) => field_conversion((index, field), variant, into_baker, true),
+            (true, true) => {
+                let elements = field_conversion((index, field), variant, into_baker, true);
+                quote! {
+                    #[cfg(feature = "macro")]
+                    #elements
+                    #[cfg(not(feature = "macro"))]
+                    #conversion
+                }
+            }
+        }
+    });
     quote! {
         #(#conversions)*
     }
@@ -154,28 +213,47 @@ fn get_path(attrs: &[Attribute], name: &str) -> Option<Path> {
         .cloned()
 }
 
-fn field_conversion((index, field): (usize, &Field)) -> proc_macro2::TokenStream {    
+/// With `elements` the holes in the elements of the field are converted with `into`, see `BakeElements`
+fn field_conversion((index, field): (usize, &Field), variant: Option<&Ident>, into_baker: bool, elements: bool) -> proc_macro2::TokenStream {    
     let bake_as = get_path(&field.attrs, "bake_via");
 
     let ident = field.ident.clone().unwrap_or_else(|| Ident::new(format!("x_{}", index).as_str(), Span::call_site()));
 
+    let name = field.ident.as_ref().map_or_else(|| index.to_string(), Ident::to_string);
+    let variant = variant.map(|variant| {
+        let variant = variant.to_string();
+        quote!(.within(struct_baker::path::PathSegment::Variant(#variant.to_owned())))
+    });
+    let within = quote!(.map_err(|e| e.within(struct_baker::path::PathSegment::Field(#name.to_owned())) #variant)?);
+
     if let Some(alias) = bake_as.clone() {
         if field.ty == Type::Path(TypePath { qself: None, path: alias }) {
             let u = &field.ty;
-            return quote!(let #ident = struct_baker::util::quote!(#u););
+            return match into_baker {
+                true => quote!(let #ident = baker.tokens(struct_baker::util::quote!(#u));),
+                false => quote!(let #ident = struct_baker::util::quote!(#u);),
+            };
         }
     }
 
+    let value = match bake_as {
+        Some(alias) => quote!(&Into::<#alias>::into(#ident)),
+        None => quote!(#ident),
+    };
 
-    match bake_as {
-        Some(alias) => quote!(let #ident = Into::<#alias>::into(#ident).bake();),
-        None => quote!(let #ident = #ident.bake();),
+    match (into_baker, elements) {
+        (true, true) => quote! {
+            let #ident = baker.tokens(struct_baker::interpolation::BakeElements::try_bake_elements(#value, baker.context()) #within);
+        },
+        (true, false) => quote!(let #ident = struct_baker::TryBake::try_bake_into(#value, baker) #within;),
+        (false, true) => quote!(let #ident = struct_baker::interpolation::BakeElements::try_bake_elements(#value, context) #within;),
+        (false, false) => quote!(let #ident = struct_baker::TryBake::try_bake_with(#value, context) #within;),
     }
 
 }
 
 
-fn determine_idents(fields: &Fields) -> Vec<proc_macro2::TokenStream> {
+pub(crate) fn determine_idents(fields: &Fields) -> Vec<proc_macro2::TokenStream> {
     match fields {
         Fields::Named(fields) => fields
             .named
@@ -192,20 +270,21 @@ fn determine_idents(fields: &Fields) -> Vec<proc_macro2::TokenStream> {
     }
 }
 
-fn inplace_enum(ident: &Ident, alias: Option<Path>, data: DataEnum) -> proc_macro2::TokenStream {
-    let variants = enum_variants(ident, alias, &data);
+fn inplace_enum(ident: &Ident, alias: Option<Path>, data: &DataEnum, elements: ElementFields) -> proc_macro2::TokenStream {
+    let variants = enum_variants(ident, alias, data, elements);
 
     quote!(match object {
         #(#variants),*
     })
 }
 
-fn enum_variants(ident: &Ident, alias: Option<Path>, data: &DataEnum) -> Vec<proc_macro2::TokenStream> {
+fn enum_variants(ident: &Ident, alias: Option<Path>, data: &DataEnum, elements: ElementFields) -> Vec<proc_macro2::TokenStream> {
     data.variants
         .iter()
-        .map(|variant| {
+        .enumerate()
+        .map(|(index, variant)| {
             let destructured = destructure(&variant.fields);
-            let conversion = convert(&variant.fields);
+            let conversion = convert(&variant.fields, Some(&variant.ident), false, elements.of(index), elements.macro_only);
             let restructured = restructure(&variant.fields);
             let var_ident = &variant.ident;
 
@@ -223,7 +302,7 @@ fn enum_variants(ident: &Ident, alias: Option<Path>, data: &DataEnum) -> Vec<pro
                     quote! {
                         Self :: #var_ident #destructured => {
                             #conversion
-                            let #path: struct_baker::util::Path = struct_baker::util::parse_str(module_path!()).unwrap();
+                            let #path = context.module_path(module_path!())?;
 
                             struct_baker::util::quote!(##path :: #ident :: #var_ident #restructured)
                         }
@@ -234,4 +313,69 @@ fn enum_variants(ident: &Ident, alias: Option<Path>, data: &DataEnum) -> Vec<pro
             
         })
         .collect()
-}
\ No newline at end of file
+}
+/// The baked fields as `struct_baker::baker::Fields`
+fn baker_fields(fields: &Fields) -> proc_macro2::TokenStream {
+    let idents = determine_idents(fields);
+
+    match fields {
+        Fields::Named(_) => {
+            let names = idents.iter().map(ToString::to_string);
+            quote!(struct_baker::baker::Fields::Named(vec![#((#names.to_owned(), #idents)),*]))
+        }
+        Fields::Unnamed(_) => quote!(struct_baker::baker::Fields::Unnamed(vec![#(#idents),*])),
+        Fields::Unit => quote!(struct_baker::baker::Fields::Unit),
+    }
+}
+
+/// The path of the baked type as tokens for the baker
+fn baker_path(ident: &Ident, alias: Option<&Path>) -> proc_macro2::TokenStream {
+    match alias {
+        Some(alias) => quote!(let __path = struct_baker::util::quote!(#alias);),
+        None => {
+            let path = Ident::new("__path", Span::call_site());
+            quote! {
+                let #path = baker.context().module_path(module_path!())?;
+                let #path = struct_baker::util::quote!(##path :: #ident);
+            }
+        }
+    }
+}
+
+fn baker_struct(ident: &Ident, alias: Option<Path>, data: &DataStruct, elements: ElementFields) -> proc_macro2::TokenStream {
+    let destructured = destructure(&data.fields);
+    let conversion = convert(&data.fields, None, true, elements.of(0), elements.macro_only);
+    let path = baker_path(ident, alias.as_ref());
+    let fields = baker_fields(&data.fields);
+
+    quote!(
+        let Self #destructured = object;
+
+        #conversion
+        #path
+        baker.structure(__path, #fields)
+    )
+}
+
+fn baker_enum(ident: &Ident, alias: Option<Path>, data: &DataEnum, elements: ElementFields) -> proc_macro2::TokenStream {
+    let path = baker_path(ident, alias.as_ref());
+    let variants = data.variants.iter().enumerate().map(|(index, variant)| {
+        let destructured = destructure(&variant.fields);
+        let conversion = convert(&variant.fields, Some(&variant.ident), true, elements.of(index), elements.macro_only);
+        let fields = baker_fields(&variant.fields);
+        let var_ident = &variant.ident;
+        let name = var_ident.to_string();
+
+        quote! {
+            Self :: #var_ident #destructured => {
+                #conversion
+                #path
+                baker.variant(__path, #name, #fields)
+            }
+        }
+    });
+
+    quote!(match object {
+        #(#variants),*
+    })
+}
@@ -0,0 +1,214 @@
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syn::{Data, DeriveInput, Field, Fields, Path, Type};
+
+use crate::{
+    derive::{destructure, determine_idents, get_attrs},
+    find_path_arg,
+};
+
+pub fn generate_impl(derive_input: DeriveInput) -> TokenStream {
+    let DeriveInput {
+        attrs,
+        ident,
+        generics,
+        data,
+        ..
+    } = derive_input;
+
+    let target = find_path_arg(&get_attrs(&attrs, "flatten"), "into");
+
+    let arms: Vec<_> = match data {
+        Data::Struct(data) => vec![flatten_arm(quote!(Self), &ident, target.as_ref(), None, &data.fields)],
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| {
+                let var_ident = &variant.ident;
+                flatten_arm(
+                    quote!(Self :: #var_ident),
+                    &ident,
+                    target.as_ref(),
+                    Some(var_ident),
+                    &variant.fields,
+                )
+            })
+            .collect(),
+        Data::Union(_) => panic!("Unions can not be flattened"),
+    };
+
+    let target = match &target {
+        Some(target) => quote!(#target),
+        None => quote!(Self),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics struct_baker::interpolation::FlattenInterpolation<#target> for #ident #ty_generics #where_clause {
+            fn flatten_interpolation(self) -> struct_baker::interpolation::Interpolatable<#target> {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+}
+
+/// Whether the type of a field is written as `Interpolatable<_>`
+fn is_interpolatable(field: &Field) -> bool {
+    match &field.ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "Interpolatable")
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+/// Creates the match arm for a single struct or variant
+///
+/// The result is an `Actual` if all interpolatable fields are `Actual`, otherwise
+/// the struct is baked and every interpolatable field gets converted with `into()`
+fn flatten_arm(
+    pattern: TokenStream,
+    ident: &Ident,
+    target: Option<&Path>,
+    variant: Option<&Ident>,
+    fields: &Fields,
+) -> TokenStream {
+    let idents = determine_idents(fields);
+    let field_list: Vec<_> = fields.iter().collect();
+
+    let actual_patterns = idents.iter().zip(&field_list).map(|(ident, field)| {
+        match (fields, is_interpolatable(field)) {
+            (Fields::Named(_), true) => quote!(#ident: struct_baker::interpolation::Interpolatable::Actual(#ident)),
+            (_, true) => quote!(struct_baker::interpolation::Interpolatable::Actual(#ident)),
+            (_, false) => quote!(#ident),
+        }
+    });
+
+    let actual_pattern = match fields {
+        Fields::Named(_) => quote!(#pattern { #(#actual_patterns),* }),
+        Fields::Unnamed(_) => quote!(#pattern ( #(#actual_patterns),* )),
+        Fields::Unit => pattern.clone(),
+    };
+
+    let variant = variant.map(|variant| quote!(:: #variant));
+
+    // A struct flattened into itself keeps its interpolatable fields
+    let actual_fields = idents.iter().zip(&field_list).map(|(ident, field)| {
+        match (fields, target.is_none() && is_interpolatable(field)) {
+            (Fields::Named(_), true) => quote!(#ident: struct_baker::interpolation::Interpolatable::Actual(#ident)),
+            (_, true) => quote!(struct_baker::interpolation::Interpolatable::Actual(#ident)),
+            (_, false) => quote!(#ident),
+        }
+    });
+
+    let actual_fields = match fields {
+        Fields::Named(_) => quote!({ #(#actual_fields),* }),
+        Fields::Unnamed(_) => quote!(( #(#actual_fields),* )),
+        Fields::Unit => quote!(),
+    };
+
+    let actual = match target {
+        Some(target) => quote!(#target #variant #actual_fields),
+        None => quote!(Self #variant #actual_fields),
+    };
+
+    let actual_arm = quote!(#actual_pattern => struct_baker::interpolation::Interpolatable::Actual(#actual),);
+
+    if !field_list.iter().any(|field| is_interpolatable(field)) {
+        return actual_arm;
+    }
+
+    let destructured = destructure(fields);
+
+    let baked_fields = idents.iter().zip(&field_list).map(|(ident, field)| {
+        let value = match is_interpolatable(field) {
+            true => quote!({# #ident}.into()),
+            false => quote!(# #ident),
+        };
+        match fields {
+            Fields::Named(_) => quote!(#ident: #value),
+            _ => value,
+        }
+    });
+
+    let restructured = match fields {
+        Fields::Named(_) => quote!({ #(#baked_fields),* }),
+        Fields::Unnamed(_) => quote!(( #(#baked_fields),* )),
+        Fields::Unit => quote!(),
+    };
+
+    // A single identifier lives next to the derived type, longer paths are used as written
+    let (module_path, target_path) = match target {
+        Some(target) if target.get_ident().is_none() => (quote!(), quote!(#target)),
+        target => {
+            let target_ident = target.map_or_else(|| quote!(#ident), |target| quote!(#target));
+            let path = Ident::new("__path", Span::call_site());
+            (
+                quote!(let #path: struct_baker::util::Path = struct_baker::util::parse_str(module_path!()).unwrap();),
+                quote!(##path :: #target_ident),
+            )
+        }
+    };
+
+    quote! {
+        #actual_arm
+        #pattern #destructured => {
+            #(let #idents = struct_baker::Bakeable::bake(&#idents);)*
+            #module_path
+
+            struct_baker::interpolation::Interpolatable::Inter(struct_baker::util::parse_quote!({
+                #target_path #variant #restructured
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn flatten_into() {
+        let input: DeriveInput = parse_quote! {
+            #[flatten(into(Point))]
+            struct PointHoles {
+                x: Interpolatable<i64>,
+                y: i64,
+            }
+        };
+        let expansion = generate_impl(input).to_string();
+        let header = quote!(struct_baker::interpolation::FlattenInterpolation<Point> for PointHoles);
+        assert!(expansion.contains(&header.to_string()), "{expansion}");
+        let actual = quote! {
+            Self { x: struct_baker::interpolation::Interpolatable::Actual(x), y } =>
+                struct_baker::interpolation::Interpolatable::Actual(Point { x, y }),
+        };
+        assert!(expansion.contains(&actual.to_string()), "{expansion}");
+        assert!(expansion.contains("# __path :: Point { x : { # x } . into () , y : # y }"), "{expansion}");
+    }
+
+    #[test]
+    fn flatten_into_path() {
+        let input: DeriveInput = parse_quote! {
+            #[flatten(into = shapes::Shape)]
+            enum ShapeHoles {
+                Circle(Interpolatable<i64>),
+                Empty,
+            }
+        };
+        let expansion = generate_impl(input).to_string();
+        assert!(expansion.contains(&quote!(FlattenInterpolation<shapes::Shape>).to_string()), "{expansion}");
+        assert!(expansion.contains("shapes :: Shape :: Circle ({ # x_0 } . into ())"), "{expansion}");
+        assert!(!expansion.contains("module_path"), "{expansion}");
+        let empty = quote!(Self::Empty => struct_baker::interpolation::Interpolatable::Actual(shapes::Shape::Empty),);
+        assert!(expansion.contains(&empty.to_string()), "{expansion}");
+    }
+}
@@ -1,12 +1,25 @@
 use quote::quote;
-use syn::{Attribute, DataEnum, DataStruct, Field, Fields, LitBool, Variant};
+use syn::{
+    parse_quote, Attribute, Data, DataEnum, DataStruct, Field, Fields, GenericArgument, Ident, LitBool,
+    PathArguments, PathSegment, Type, Variant,
+};
 
 use crate::BakeInfo;
 
+/// How a field is interpolated in macro mode
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Interpolation {
+    None,
+    /// `T` becomes `Interpolatable<T>`
+    Whole,
+    /// `Vec<T>` becomes `Vec<Interpolatable<T>>`, `[T; N]` becomes `[Interpolatable<T>; N]`
+    Elements,
+}
+
 pub(crate) fn interpolate_struct(
     input: BakeInfo,
     data: DataStruct,
-    interpolate_all: bool,
+    interpolate_all: Interpolation,
 ) -> proc_macro2::TokenStream {
     let BakeInfo {
         attrs,
@@ -24,7 +37,7 @@ pub(crate) fn interpolate_struct(
     )
 }
 
-pub(crate) fn interpolate_enum(input: BakeInfo, data: DataEnum, interpolate_all: bool) -> proc_macro2::TokenStream {
+pub(crate) fn interpolate_enum(input: BakeInfo, data: DataEnum, interpolate_all: Interpolation) -> proc_macro2::TokenStream {
     let BakeInfo {
         attrs,
         vis,
@@ -67,7 +80,7 @@ pub(crate) fn interpolate_enum(input: BakeInfo, data: DataEnum, interpolate_all:
     }
 }
 
-fn interpolate_struct_content(fields: Fields, interpolate_all: bool) -> proc_macro2::TokenStream {
+fn interpolate_struct_content(fields: Fields, interpolate_all: Interpolation) -> proc_macro2::TokenStream {
     match fields {
         syn::Fields::Named(fields) => {
             let fields: Vec<_> = fields
@@ -89,31 +102,175 @@ fn interpolate_struct_content(fields: Fields, interpolate_all: bool) -> proc_mac
     }
 }
 
-fn should_interpolate(attrs: &mut Vec<Attribute>, interpolate_all: bool) -> bool {
+pub(crate) fn should_interpolate(attrs: &mut Vec<Attribute>, interpolate_all: Interpolation) -> Interpolation {
     if let Some(index) = attrs
         .iter()
         .position(|attr| attr.path.is_ident("interpolate"))
     {
         let att = attrs.swap_remove(index);
 
-        match att.parse_meta().unwrap() {
+        let enabled = match att.parse_meta().unwrap() {
             syn::Meta::Path(_) => true,
             syn::Meta::List(_) => {
+                if let Ok(mode) = att.parse_args::<Ident>() {
+                    if mode == "elements" {
+                        return Interpolation::Elements;
+                    }
+                    panic!("Unknown interpolation mode `{mode}`, expected `elements`")
+                }
                 att.parse_args::<LitBool>()
-                    .expect("Only boolean arguments are allowed")
+                    .expect("Only boolean arguments or `elements` are allowed")
                     .value
             }
             syn::Meta::NameValue(named) => match named.lit {
                 syn::Lit::Bool(LitBool { value, .. }) => value,
                 _ => panic!("Only boolean arguments are allowed"),
             },
+        };
+
+        if enabled {
+            Interpolation::Whole
+        } else {
+            Interpolation::None
         }
     } else {
         interpolate_all
     }
 }
 
-fn interpolate_named_field(field: Field, interpolate_all: bool) -> proc_macro2::TokenStream {
+/// Removes all `#[interpolate]` attributes and returns the mode of every field, grouped by variant
+pub(crate) fn field_modes(data: &mut Data, interpolate_all: Interpolation) -> Vec<Vec<Interpolation>> {
+    fields_mut(data)
+        .into_iter()
+        .map(|(variant_attrs, fields)| {
+            let default = match variant_attrs {
+                Some(attrs) => should_interpolate(attrs, interpolate_all),
+                None => interpolate_all,
+            };
+            fields
+                .iter_mut()
+                .map(|field| should_interpolate(&mut field.attrs, default))
+                .collect()
+        })
+        .collect()
+}
+
+/// The attributes of every variant, `None` for structs, together with its fields
+pub(crate) fn fields_mut(data: &mut Data) -> Vec<(Option<&mut Vec<Attribute>>, &mut Fields)> {
+    match data {
+        Data::Struct(data) => vec![(None, &mut data.fields)],
+        Data::Enum(data) => data
+            .variants
+            .iter_mut()
+            .map(|variant| (Some(&mut variant.attrs), &mut variant.fields))
+            .collect(),
+        Data::Union(_) => panic!("Unions can not be interpolated"),
+    }
+}
+
+/// The name of every variant, `None` for structs, together with its fields
+pub(crate) fn fields_of(data: &Data) -> Vec<(Option<&Ident>, &Fields)> {
+    match data {
+        Data::Struct(data) => vec![(None, &data.fields)],
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| (Some(&variant.ident), &variant.fields))
+            .collect(),
+        Data::Union(_) => panic!("Unions can not be interpolated"),
+    }
+}
+
+/// The type of a field in macro mode or `None` if the field is not interpolated
+pub(crate) fn interpolated_type(ty: &Type, interpolation: Interpolation) -> Option<Type> {
+    match interpolation {
+        Interpolation::None => None,
+        Interpolation::Whole => Some(parse_quote!(struct_baker::interpolation::Interpolatable<#ty>)),
+        Interpolation::Elements => Some(interpolate_elements(ty)),
+    }
+}
+
+/// Wraps the element type of a container in `Interpolatable`
+///
+/// Only the values of maps are interpolated, other type arguments like the hasher of a `HashMap` are kept.
+fn interpolate_elements(ty: &Type) -> Type {
+    let mut ty = ty.clone();
+
+    match &mut ty {
+        Type::Array(array) => {
+            let elem = &array.elem;
+            *array.elem = parse_quote!(struct_baker::interpolation::Interpolatable<#elem>);
+        }
+        Type::Path(path) => {
+            let segment = path.path.segments.last_mut().expect("Type paths have at least one segment");
+            let position = element_position(&segment.ident);
+            let element = type_arguments(segment)
+                .into_iter()
+                .nth(position)
+                .expect("`#[interpolate(elements)]` requires the type arguments of the container");
+            *element = parse_quote!(struct_baker::interpolation::Interpolatable<#element>);
+        }
+        _ => panic!("`#[interpolate(elements)]` can only be used on arrays and std containers"),
+    }
+
+    ty
+}
+
+/// The position of the interpolated type argument of a container
+fn element_position(container: &Ident) -> usize {
+    match container.to_string().as_str() {
+        "Vec" | "VecDeque" | "LinkedList" | "Option" | "Box" => 0,
+        "BTreeMap" | "HashMap" => 1,
+        other => panic!(
+            "`#[interpolate(elements)]` can not be used on `{other}`, only on arrays, `Vec`, `VecDeque`, `LinkedList`, `Option`, `Box`, `BTreeMap` and `HashMap`"
+        ),
+    }
+}
+
+fn type_arguments(segment: &mut PathSegment) -> Vec<&mut Type> {
+    match &mut segment.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter_mut()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The type that borrows the elements of a container with `#[interpolate(elements)]`
+///
+/// Elements become references, maps keep their keys and values but lose their hasher and a `Box` becomes a single reference.
+pub(crate) fn element_refs(ty: &Type) -> Type {
+    let mut ty = ty.clone();
+
+    match &mut ty {
+        Type::Array(array) => {
+            let elem = &array.elem;
+            *array.elem = parse_quote!(&#elem);
+        }
+        Type::Path(path) => {
+            let segment = path.path.segments.last_mut().expect("Type paths have at least one segment");
+            let count = element_position(&segment.ident) + 1;
+            let arguments: Vec<Type> = type_arguments(segment).into_iter().take(count).map(|ty| parse_quote!(&#ty)).collect();
+            if arguments.len() != count {
+                panic!("`#[interpolate(elements)]` requires the type arguments of the container");
+            }
+            if segment.ident == "Box" {
+                return arguments[0].clone();
+            }
+            segment.arguments = PathArguments::AngleBracketed(parse_quote!(<#(#arguments),*>));
+        }
+        _ => panic!("`#[interpolate(elements)]` can only be used on arrays and std containers"),
+    }
+
+    ty
+}
+
+fn interpolate_named_field(field: Field, interpolate_all: Interpolation) -> proc_macro2::TokenStream {
     let Field {
         mut attrs,
         ident,
@@ -122,11 +279,11 @@ fn interpolate_named_field(field: Field, interpolate_all: bool) -> proc_macro2::
         ..
     } = field;
 
-    if should_interpolate(&mut attrs, interpolate_all) {
+    if let Some(interpolated) = interpolated_type(&ty, should_interpolate(&mut attrs, interpolate_all)) {
         quote! {
             #(#attrs)*
             #[cfg(feature = "macro")]
-            #vis #ident : struct_baker::interpolation::Interpolatable<#ty>,
+            #vis #ident : #interpolated,
             #[cfg(not(feature = "macro"))]
             #vis #ident : #ty,
         }
@@ -138,16 +295,16 @@ fn interpolate_named_field(field: Field, interpolate_all: bool) -> proc_macro2::
     }
 }
 
-fn interpolate_unnamed_field(field: Field, interpolate_all: bool) -> proc_macro2::TokenStream {
+fn interpolate_unnamed_field(field: Field, interpolate_all: Interpolation) -> proc_macro2::TokenStream {
     let Field {
         mut attrs, ty, vis, ..
     } = field;
 
-    if should_interpolate(&mut attrs, interpolate_all) {
+    if let Some(interpolated) = interpolated_type(&ty, should_interpolate(&mut attrs, interpolate_all)) {
         quote! {
             #(#attrs)*
             #[cfg(feature = "macro")]
-            #vis struct_baker::interpolation::Interpolatable<#ty>,
+            #vis #interpolated,
             #[cfg(not(feature = "macro"))]
             #vis #ty,
         }
@@ -158,3 +315,85 @@ fn interpolate_unnamed_field(field: Field, interpolate_all: bool) -> proc_macro2
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+    use syn::DeriveInput;
+
+    use super::*;
+
+    fn elements(ty: Type) -> String {
+        interpolated_type(&ty, Interpolation::Elements).unwrap().to_token_stream().to_string()
+    }
+
+    #[test]
+    fn element_types() {
+        let hole = quote!(struct_baker::interpolation::Interpolatable);
+        assert_eq!(elements(parse_quote!(Vec<Json>)), quote!(Vec<#hole<Json> >).to_string());
+        assert_eq!(elements(parse_quote!([Json; 3])), quote!([#hole<Json>; 3]).to_string());
+        assert_eq!(
+            elements(parse_quote!(HashMap<String, Json, S>)),
+            quote!(HashMap<String, #hole<Json>, S>).to_string()
+        );
+        assert_eq!(
+            element_refs(&parse_quote!(HashMap<String, Json, S>)).to_token_stream().to_string(),
+            quote!(HashMap<&String, &Json>).to_string()
+        );
+        assert_eq!(element_refs(&parse_quote!(Box<Json>)).to_token_stream().to_string(), "& Json");
+    }
+
+    #[test]
+    #[should_panic(expected = "can not be used on `Rc`")]
+    fn unknown_containers() {
+        elements(parse_quote!(Rc<Json>));
+    }
+
+    #[test]
+    fn modes_by_position() {
+        let mut input: DeriveInput = parse_quote! {
+            enum Json {
+                #[interpolate]
+                Pair(i64, #[interpolate(false)] i64),
+                List(#[interpolate(elements)] Vec<Json>),
+                Plain(i64),
+            }
+        };
+        let modes = field_modes(&mut input.data, Interpolation::None);
+        assert!(modes == [
+            vec![Interpolation::Whole, Interpolation::None],
+            vec![Interpolation::Elements],
+            vec![Interpolation::None],
+        ]);
+        // The attributes are consumed
+        assert!(!input.to_token_stream().to_string().contains("interpolate"));
+    }
+
+    #[test]
+    fn macro_mode_fields() {
+        let input: DeriveInput = parse_quote! {
+            struct List {
+                #[interpolate(elements)]
+                items: Vec<u8>,
+                len: usize,
+            }
+        };
+        let Data::Struct(data) = input.data else { unreachable!() };
+        let info = BakeInfo {
+            attrs: input.attrs,
+            vis: input.vis,
+            path: input.ident.into(),
+            generics: input.generics,
+        };
+        let expected = quote! {
+            struct List {
+                #[cfg(feature = "macro")]
+                items: Vec<struct_baker::interpolation::Interpolatable<u8> >,
+                #[cfg(not(feature = "macro"))]
+                items: Vec<u8>,
+                len: usize,
+            }
+        };
+        assert_eq!(interpolate_struct(info, data, Interpolation::None).to_string(), expected.to_string());
+    }
+}
@@ -1,13 +1,18 @@
 use proc_macro::TokenStream;
 
+use proc_macro2::TokenTree;
 use quote::{quote, ToTokens};
 use syn::{
-    parse_macro_input, AttributeArgs, Data, DeriveInput,
-    Expr, Ident, NestedMeta, Path, Meta, Attribute, Visibility, Generics,
+    parse::Parser, parse_macro_input, punctuated::Punctuated, AttributeArgs, Data, DeriveInput,
+    Expr, ExprClosure, Ident, NestedMeta, Path, Meta, Attribute, Visibility, Generics, Token,
 };
 
 mod derive;
+mod flatten;
 mod interpolation;
+mod methods;
+mod template;
+mod visit;
 
 #[proc_macro_derive(Bake, attributes(interpolate, bake_via))]
 pub fn derive_bake(input: TokenStream) -> TokenStream {
@@ -16,6 +21,20 @@ pub fn derive_bake(input: TokenStream) -> TokenStream {
     derive::generate_impl(derive_input).into()
 }
 
+#[proc_macro_derive(FlattenInterpolation, attributes(flatten))]
+pub fn derive_flatten_interpolation(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+
+    flatten::generate_impl(derive_input).into()
+}
+
+#[proc_macro_derive(VisitInterpolations)]
+pub fn derive_visit_interpolations(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+
+    visit::generate_impl(derive_input).into()
+}
+
 #[proc_macro_attribute]
 pub fn bake_new(args: TokenStream, input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -103,6 +122,53 @@ fn find_arg<'a>(args: &'a AttributeArgs, name: &str) -> Option<&'a NestedMeta> {
     })
 }
 
+/// Finds an argument like `name(A, b::C)` and returns all paths
+fn find_list_arg(args: &AttributeArgs, name: &str) -> Vec<Path> {
+    match find_arg(args, name) {
+        Some(NestedMeta::Meta(Meta::List(list))) => list
+            .nested
+            .iter()
+            .map(|nested| match nested {
+                NestedMeta::Meta(Meta::Path(path)) => path.clone(),
+                _ => panic!("`{name}` only accepts paths"),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Parses the arguments of an attribute, `name = some::Path` is accepted as `name(some::Path)`
+///
+/// syn only allows literals after `=` in a `Meta`, so these arguments are rewritten before they are parsed.
+pub(crate) fn parse_args(tokens: proc_macro2::TokenStream) -> syn::Result<AttributeArgs> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let args = tokens
+        .split(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ','))
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| match arg {
+            [TokenTree::Ident(name), TokenTree::Punct(eq), value @ ..]
+                if eq.as_char() == '=' && !matches!(value, [TokenTree::Literal(_)]) =>
+            {
+                let value: proc_macro2::TokenStream = value.iter().cloned().collect();
+                quote!(#name(#value))
+            }
+            _ => arg.iter().cloned().collect(),
+        });
+    let args = quote!(#(#args),*);
+    Ok(Punctuated::<NestedMeta, Token![,]>::parse_terminated.parse2(args)?.into_iter().collect())
+}
+
+/// Finds an argument like `name(some::Path)` or `name = some::Path` and returns the path
+fn find_path_arg(args: &AttributeArgs, name: &str) -> Option<Path> {
+    match find_arg(args, name) {
+        Some(NestedMeta::Meta(Meta::List(list))) => match list.nested.first() {
+            Some(NestedMeta::Meta(Meta::Path(path))) => Some(path.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[derive(Clone)]
 struct BakeInfo {
     /// Attributes tagged on the whole struct or enum.
@@ -123,7 +189,10 @@ struct BakeInfo {
 #[proc_macro_attribute]
 pub fn bake(args: TokenStream, input: TokenStream) -> TokenStream {
     let DeriveInput { attrs, vis, ident, generics, data } = parse_macro_input!(input as DeriveInput);
-    let args = parse_macro_input!(args as AttributeArgs);
+    let args = match parse_args(args.into()) {
+        Ok(args) => args,
+        Err(e) => return e.to_compile_error().into(),
+    };
 
     let path = ident.clone().into();
 
@@ -131,13 +200,45 @@ pub fn bake(args: TokenStream, input: TokenStream) -> TokenStream {
         attrs, vis, path, generics
     };
 
-    let interpolate_all = find_arg(&args, "interpolate").is_some();
+    let interpolate_all = match find_arg(&args, "interpolate") {
+        Some(_) => interpolation::Interpolation::Whole,
+        None => interpolation::Interpolation::None,
+    };
+
 
+    let template = find_path_arg(&args, "template")
+        .map(|template| template.get_ident().expect("`template` expects the name of the new type").clone());
 
-    let main_impl = match data {
-            Data::Struct(data) => interpolation::interpolate_struct(info.clone(), data, interpolate_all),
-            Data::Enum(data) => interpolation::interpolate_enum(info.clone(), data, interpolate_all),
-            Data::Union(_) => todo!(),
+    let constructors = find_arg(&args, "constructors").is_some();
+    let accessors = find_arg(&args, "accessors").is_some();
+    let methods = if constructors || accessors {
+        let mut stripped = data.clone();
+        let modes = interpolation::field_modes(&mut stripped, interpolate_all);
+        let path = template.clone().map(Path::from).unwrap_or_else(|| info.path.clone());
+        methods::generate(&path, &info.generics, &stripped, &modes, constructors, accessors)
+    } else {
+        quote!()
+    };
+
+    let visit_impl = match find_arg(&args, "visit") {
+        Some(_) => {
+            let ident = info.path.get_ident().expect("Type name");
+            let twin = template.as_ref().map(|template| visit::visit_impl(template, &info.generics, &data));
+            let original = visit::visit_impl(ident, &info.generics, &data);
+            quote!(#original #twin)
+        }
+        None => quote!(),
+    };
+
+    let main_impl = match (template, data) {
+            (Some(template), data) => {
+                let derives = find_list_arg(&args, "template_derive");
+                let alias = find_path_arg(&args, "bake_as");
+                template::generate(info.clone(), data, interpolate_all, template, derives, alias)
+            },
+            (None, Data::Struct(data)) => interpolation::interpolate_struct(info.clone(), data, interpolate_all),
+            (None, Data::Enum(data)) => interpolation::interpolate_enum(info.clone(), data, interpolate_all),
+            (None, Data::Union(_)) => todo!(),
     };
 
     let to_tokens_impl = find_arg(&args, "to_tokens").map(|_| to_tokens(info)).unwrap_or_default();
@@ -145,6 +246,10 @@ pub fn bake(args: TokenStream, input: TokenStream) -> TokenStream {
     quote!{
         #main_impl
 
+        #methods
+
+        #visit_impl
+
         #to_tokens_impl
     }.into()
 
@@ -178,33 +283,59 @@ fn to_tokens(input: BakeInfo) -> proc_macro2::TokenStream {
 //     }
 // }
 
+/// Defines a proc macro `name` that parses its input with the `FromStr` impl of the annotated type
+///
+/// Only kept for compatibility, the macro is created like [parser_macro!] would with a parser that calls `from_str`.
+/// The macro is emitted next to the type, so the type has to be defined at the root of a proc-macro crate.
+/// `FromStr` errors have no location and are reported at the start of the input.
+///
+/// [parser_macro!]: https://docs.rs/struct_baker/latest/struct_baker/macro.parser_macro.html
+#[deprecated(
+    note = "only works on types at the root of a proc-macro crate, use `struct_baker::parser_macro!` with a parser function instead"
+)]
 #[proc_macro_attribute]
 pub fn ct_parser(args: TokenStream, input: TokenStream) -> TokenStream {
-    let macro_name: Ident = syn::parse(args).unwrap();
-
+    let macro_name = parse_macro_input!(args as Ident);
     let input = parse_macro_input!(input as DeriveInput);
-    let type_name = input.ident.clone();
-    let struc = input.into_token_stream();
+    let type_name = &input.ident;
 
     quote!(
+        #input
+
         #[proc_macro]
         pub fn #macro_name(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-            quote::quote!(
-                <#type_name as FromStr>::from_str(stringify!(input)).unwrap().bake()
-            ).into()
+            struct_baker::parser::expand(input.into(), |text: &str| {
+                <#type_name as ::std::str::FromStr>::from_str(text)
+                    .map(struct_baker::interpolation::Interpolatable::Actual)
+                    .map_err(|e| struct_baker::parser::ParseError::new(0, e))
+            })
+            .into()
         }
-
-        #struc
     )
     .into()
 }
 
-#[proc_macro]
 /// Construct a new BakeableFnOnce
+#[proc_macro]
 pub fn bake_fn_once(input: TokenStream) -> TokenStream {
     let path = parse_macro_input!(input as Path);
 
     quote!({
         struct_baker::functions::BakeableFnOnce::_new(#path, struct_baker::util::parse_quote!(#path))
     }).into()
-}
\ No newline at end of file
+}
+
+/// Construct a new BakeableFnOnce from a closure like `|x: i64| x * 2`
+///
+/// The closure is compiled as-is and its tokens are baked, so it must not capture any variables.
+#[proc_macro]
+pub fn bake_map(input: TokenStream) -> TokenStream {
+    let closure = parse_macro_input!(input as ExprClosure);
+
+    quote!({
+        struct_baker::functions::BakeableFnOnce::_from_tokens(
+            #closure,
+            struct_baker::util::parse_quote!((#closure))
+        )
+    }).into()
+}
@@ -0,0 +1,240 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{ext::IdentExt, Data, Fields, Generics, Ident, Path, Type};
+
+use crate::{
+    derive::{destructure, determine_idents},
+    interpolation::{element_refs, fields_of, interpolated_type, Interpolation},
+};
+
+/// Constructors and accessors that compile the same way with and without the `macro` feature
+pub(crate) fn generate(
+    path: &Path,
+    generics: &Generics,
+    data: &Data,
+    modes: &[Vec<Interpolation>],
+    constructors: bool,
+    accessors: bool,
+) -> TokenStream {
+    let mut methods = Vec::new();
+
+    for ((variant, fields), modes) in fields_of(data).into_iter().zip(modes) {
+        if constructors {
+            methods.extend(constructor(variant, fields, modes));
+        }
+        if accessors {
+            match variant {
+                Some(variant) => methods.extend(variant_accessor(variant, fields, modes)),
+                None => methods.extend(struct_accessors(fields, modes)),
+            }
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #path #ty_generics #where_clause {
+            #(#methods)*
+        }
+    }
+}
+
+/// Converts a variant name to snake case, runs of capitals are one word so `HTTPServer` becomes `http_server`
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            let previous = chars[index - 1];
+            let next_is_lower = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase() || previous.is_numeric() || (previous.is_uppercase() && next_is_lower) {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+/// Creates an identifier, using a raw identifier for keywords like `type`
+///
+/// `self`, `super`, `crate` and `Self` can not be raw identifiers and get a trailing underscore instead.
+fn method_ident(name: &str) -> Ident {
+    match name {
+        "self" | "super" | "crate" | "Self" => format_ident!("{name}_"),
+        _ => syn::parse_str(name).unwrap_or_else(|_| Ident::new_raw(name, Span::call_site())),
+    }
+}
+
+/// The borrowed type of a field and how to borrow it, holes result in an error
+fn borrow(member: TokenStream, ty: &Type, mode: Interpolation) -> (TokenStream, TokenStream) {
+    match mode {
+        Interpolation::Elements => {
+            let refs = element_refs(ty);
+            (quote!(#refs), quote!(struct_baker::interpolation::FitElementsRef::fit_elements_ref(#member)))
+        }
+        _ => (quote!(&#ty), quote!(struct_baker::interpolation::FitRef::fit_ref(#member))),
+    }
+}
+
+fn field_types(fields: &Fields) -> Vec<&Type> {
+    fields.iter().map(|field| &field.ty).collect()
+}
+
+/// `new` for structs and the variant name in snake case for enums
+///
+/// Interpolated fields accept anything that converts into an `Interpolatable`,
+/// holes result in an error if the type can not hold them.
+fn constructor(variant: Option<&Ident>, fields: &Fields, modes: &[Interpolation]) -> Option<TokenStream> {
+    if fields.is_empty() {
+        return None;
+    }
+
+    let (name, target) = match variant {
+        Some(variant) => (method_ident(&snake_case(&variant.to_string())), quote!(Self :: #variant)),
+        None => (format_ident!("new"), quote!(Self)),
+    };
+
+    let idents = determine_idents(fields);
+    let types = field_types(fields);
+
+    let (parameters, values): (Vec<_>, Vec<_>) = idents
+        .iter()
+        .zip(types)
+        .zip(modes)
+        .map(|((ident, ty), mode)| match interpolated_type(ty, *mode) {
+            Some(interpolated) => (
+                quote!(#ident: impl Into<#interpolated>),
+                quote!(struct_baker::interpolation::Interpolate::fit(Into::<#interpolated>::into(#ident))?),
+            ),
+            None => (quote!(#ident: #ty), quote!(#ident)),
+        })
+        .unzip();
+
+    let value = match fields {
+        Fields::Named(_) => quote!(#target { #(#idents: #values),* }),
+        _ => quote!(#target ( #(#values),* )),
+    };
+
+    let docs = format!("Creates a [`{}`] from values or holes", target.to_string().replace(' ', ""));
+
+    Some(quote! {
+        #[doc = #docs]
+        #[allow(clippy::too_many_arguments)]
+        pub fn #name(#(#parameters),*) -> Result<Self, struct_baker::interpolation::RuntimeInterpolationError> {
+            Ok(#value)
+        }
+    })
+}
+
+/// `<field>_ref` for every interpolated field of a struct
+fn struct_accessors(fields: &Fields, modes: &[Interpolation]) -> Vec<TokenStream> {
+    fields
+        .iter()
+        .enumerate()
+        .zip(modes)
+        .filter(|(_, mode)| **mode != Interpolation::None)
+        .map(|((index, field), mode)| {
+            let (name, member) = match &field.ident {
+                Some(ident) => (format_ident!("{ident}_ref"), quote!(#ident)),
+                None => {
+                    let member = syn::Index::from(index);
+                    (format_ident!("field_{index}_ref"), quote!(#member))
+                }
+            };
+            let (ty, value) = borrow(quote!(&self.#member), &field.ty, *mode);
+
+            quote! {
+                /// Borrows the field, fails if it contains a hole
+                pub fn #name(&self) -> Result<#ty, struct_baker::interpolation::RuntimeInterpolationError> {
+                    #value
+                }
+            }
+        })
+        .collect()
+}
+
+/// `<variant>_ref` that borrows all fields of the variant as a tuple
+///
+/// Fails for other variants and if a field contains a hole.
+fn variant_accessor(variant: &Ident, fields: &Fields, modes: &[Interpolation]) -> Option<TokenStream> {
+    if fields.is_empty() {
+        return None;
+    }
+
+    let name = format_ident!("{}_ref", snake_case(&variant.to_string()));
+    let idents = determine_idents(fields);
+    let destructured = destructure(fields);
+
+    let (types, values): (Vec<_>, Vec<_>) = idents
+        .iter()
+        .zip(fields)
+        .zip(modes)
+        .map(|((ident, field), mode)| borrow(quote!(#ident), &field.ty, *mode))
+        .unzip();
+
+    let (ty, value) = match (&types[..], &values[..]) {
+        ([ty], [value]) => (quote!(#ty), quote!(#value?)),
+        _ => (quote!((#(#types),*)), quote!((#(#values?),*))),
+    };
+
+    let docs = format!("Borrows the fields of [`Self::{variant}`], fails for other variants or if any of them contains a hole");
+    let expected = variant.unraw().to_string();
+
+    Some(quote! {
+        #[doc = #docs]
+        pub fn #name(&self) -> Result<#ty, struct_baker::interpolation::AccessError> {
+            match self {
+                Self :: #variant #destructured => Ok(#value),
+                #[allow(unreachable_patterns)]
+                _ => Err(struct_baker::interpolation::AccessError::Variant { expected: #expected }),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::{parse_quote, DeriveInput};
+
+    use super::*;
+    use crate::interpolation::field_modes;
+
+    #[test]
+    fn snake_case_names() {
+        assert_eq!(snake_case("Number"), "number");
+        assert_eq!(snake_case("HTTPServer"), "http_server");
+        assert_eq!(snake_case("Ipv4Addr"), "ipv4_addr");
+        assert_eq!(snake_case("r#Type"), "r#type");
+    }
+
+    #[test]
+    fn method_idents() {
+        assert_eq!(method_ident("number").to_string(), "number");
+        assert_eq!(method_ident("type").to_string(), "r#type");
+        assert_eq!(method_ident("r#type").to_string(), "r#type");
+        assert_eq!(method_ident("super").to_string(), "super_");
+        assert_eq!(method_ident("Self").to_string(), "Self_");
+    }
+
+    #[test]
+    fn expansion() {
+        let mut input: DeriveInput = parse_quote! {
+            enum Json {
+                #[interpolate]
+                r#Type(i64),
+                Empty,
+            }
+        };
+        let modes = field_modes(&mut input.data, Interpolation::None);
+        let expansion = generate(&parse_quote!(Json), &input.generics, &input.data, &modes, true, true).to_string();
+
+        let hole = quote!(struct_baker::interpolation::Interpolatable<i64>);
+        let constructor = quote!(pub fn r#type(x_0: impl Into<#hole>));
+        assert!(expansion.contains(&constructor.to_string()), "{expansion}");
+        let accessor = quote!(pub fn r#type_ref(&self) -> Result<&i64, struct_baker::interpolation::AccessError>);
+        assert!(expansion.contains(&accessor.to_string()), "{expansion}");
+        assert!(expansion.contains(&quote!(AccessError::Variant { expected: "Type" }).to_string()), "{expansion}");
+        assert!(!expansion.contains("fn empty"), "{expansion}");
+    }
+}
@@ -0,0 +1,209 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{parse_quote, Attribute, Data, DeriveInput, Fields, Path};
+
+use crate::{
+    derive::{bake_impl, destructure, determine_idents, ElementFields},
+    interpolation::{field_modes, fields_mut, fields_of, interpolated_type, Interpolation},
+    BakeInfo,
+};
+
+/// Creates the original type without interpolation and a twin `template` type with interpolatable fields
+///
+/// The twin bakes as the original type and can be converted back with `TryFrom`,
+/// so no `macro` feature is required.
+pub(crate) fn generate(
+    info: BakeInfo,
+    mut data: Data,
+    interpolate_all: Interpolation,
+    template: Ident,
+    derives: Vec<Path>,
+    alias: Option<Path>,
+) -> TokenStream {
+    let BakeInfo {
+        attrs,
+        vis,
+        path,
+        generics,
+    } = info;
+    let ident = path.get_ident().expect("Type name").clone();
+
+    let modes = field_modes(&mut data, interpolate_all);
+
+    let original = DeriveInput {
+        attrs,
+        vis: vis.clone(),
+        ident: ident.clone(),
+        generics: generics.clone(),
+        data: data.clone(),
+    };
+
+    let mut template_data = data.clone();
+    for ((_, fields), modes) in fields_mut(&mut template_data).into_iter().zip(&modes) {
+        for (field, mode) in fields.iter_mut().zip(modes) {
+            field.attrs.retain(is_doc);
+            if let Some(ty) = interpolated_type(&field.ty, *mode) {
+                field.ty = ty;
+            }
+        }
+    }
+
+    let docs = format!("Template version of [`{ident}`] whose fields can be interpolated");
+    let mut template_attrs: Vec<Attribute> = vec![parse_quote!(#[doc = #docs])];
+    if !derives.is_empty() {
+        template_attrs.push(parse_quote!(#[derive(#(#derives),*)]));
+    }
+
+    let twin = DeriveInput {
+        attrs: template_attrs,
+        vis,
+        ident: template.clone(),
+        generics: generics.clone(),
+        data: template_data,
+    };
+
+    let elements = ElementFields { modes: &modes, macro_only: false };
+    let bake = bake_impl(&template, &ident, &generics, data.clone(), alias, elements);
+
+    let arms: Vec<_> = fields_of(&data).into_iter().zip(&modes).map(|((variant, fields), modes)| {
+        let variant = variant.map(|variant| quote!(:: #variant));
+        let idents = determine_idents(fields);
+
+        let fitted = idents.iter().zip(modes).map(|(field, mode)| match mode {
+            Interpolation::None => quote!(#field),
+            _ => quote!(struct_baker::interpolation::Interpolate::fit(#field)?),
+        });
+        let restructured = restructure(fields, &idents, fitted);
+        let destructured = destructure(fields);
+
+        let holes = idents.iter().zip(modes).filter(|(_, mode)| **mode != Interpolation::None).map(|(field, _)| {
+            quote!(struct_baker::interpolation::ContainsInterpolation::contains_interpolation(#field))
+        });
+        let pattern = match fields {
+            Fields::Named(_) => {
+                let named = idents.iter().zip(modes).filter(|(_, mode)| **mode != Interpolation::None).map(|(x, _)| x);
+                quote!({ #(#named,)* .. })
+            }
+            Fields::Unnamed(_) => {
+                let unnamed = idents.iter().zip(modes).map(|(field, mode)| match mode {
+                    Interpolation::None => quote!(_),
+                    _ => quote!(#field),
+                });
+                quote!(( #(#unnamed),* ))
+            }
+            Fields::Unit => quote!(),
+        };
+
+        (
+            quote!(#template #variant #destructured => Ok(#ident #variant #restructured),),
+            quote!(#template #variant #pattern => false #(|| #holes)*,),
+        )
+    }).collect();
+    let (try_arms, hole_arms): (Vec<_>, Vec<_>) = arms.into_iter().unzip();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        #original
+
+        #twin
+
+        #bake
+
+        impl #impl_generics ::std::convert::TryFrom<#template #ty_generics> for #ident #ty_generics #where_clause {
+            type Error = struct_baker::interpolation::RuntimeInterpolationError;
+
+            fn try_from(value: #template #ty_generics) -> Result<Self, Self::Error> {
+                match value {
+                    #(#try_arms)*
+                }
+            }
+        }
+
+        impl #impl_generics From<#template #ty_generics> for struct_baker::interpolation::Interpolatable<#ident #ty_generics> #where_clause {
+            fn from(value: #template #ty_generics) -> Self {
+                let has_holes = match &value {
+                    #(#hole_arms)*
+                };
+
+                if has_holes {
+                    struct_baker::interpolation::Interpolatable::Inter(struct_baker::Bake::to_token_tree(&value))
+                } else {
+                    struct_baker::interpolation::Interpolatable::Actual(
+                        <#ident #ty_generics as ::std::convert::TryFrom<_>>::try_from(value).expect("Template without holes")
+                    )
+                }
+            }
+        }
+    }
+}
+
+fn is_doc(attr: &Attribute) -> bool {
+    attr.path.is_ident("doc")
+}
+
+fn restructure(
+    fields: &Fields,
+    idents: &[TokenStream],
+    values: impl Iterator<Item = TokenStream>,
+) -> TokenStream {
+    match fields {
+        Fields::Named(_) => quote!({ #(#idents: #values),* }),
+        Fields::Unnamed(_) => quote!(( #(#values),* )),
+        Fields::Unit => quote!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::Visibility;
+
+    use super::*;
+    use crate::{find_path_arg, parse_args};
+
+    #[test]
+    fn template_args() {
+        let args = parse_args(quote!(template = JsonTemplate, constructors)).unwrap();
+        assert_eq!(find_path_arg(&args, "template"), Some(parse_quote!(JsonTemplate)));
+        let alias = parse_args(quote!(template(JsonTemplate))).unwrap();
+        assert_eq!(find_path_arg(&alias, "template"), Some(parse_quote!(JsonTemplate)));
+        let path = parse_args(quote!(bake_as = crate::json::Json)).unwrap();
+        assert_eq!(find_path_arg(&path, "bake_as"), Some(parse_quote!(crate::json::Json)));
+    }
+
+    #[test]
+    fn twin() {
+        let input: DeriveInput = parse_quote! {
+            enum Json {
+                #[interpolate]
+                Number(i64),
+                #[interpolate(elements)]
+                List(Vec<Json>),
+                Null,
+            }
+        };
+        let info = BakeInfo {
+            attrs: input.attrs,
+            vis: Visibility::Inherited,
+            path: parse_quote!(Json),
+            generics: input.generics,
+        };
+        let expansion = generate(info, input.data, Interpolation::None, parse_quote!(JsonTemplate), vec![], None).to_string();
+
+        let twin = quote! {
+            enum JsonTemplate {
+                Number(struct_baker::interpolation::Interpolatable<i64>),
+                List(Vec<struct_baker::interpolation::Interpolatable<Json> >),
+                Null,
+            }
+        };
+        assert!(expansion.contains(&twin.to_string()), "{expansion}");
+        assert!(expansion.contains(&quote!(enum Json { Number(i64), List(Vec<Json>), Null, }).to_string()), "{expansion}");
+        let try_from = quote!(impl ::std::convert::TryFrom<JsonTemplate> for Json);
+        assert!(expansion.contains(&try_from.to_string()), "{expansion}");
+        let fitted = quote!(JsonTemplate::Number(x_0) => Ok(Json::Number(struct_baker::interpolation::Interpolate::fit(x_0)?)),);
+        assert!(expansion.contains(&fitted.to_string()), "{expansion}");
+        assert!(expansion.contains(&quote!(JsonTemplate::Null => Ok(Json::Null),).to_string()), "{expansion}");
+        // The twin bakes as the original type, so it does not depend on the `macro` feature
+        assert!(!expansion.contains("feature"), "{expansion}");
+    }
+}
@@ -0,0 +1,117 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Generics};
+
+use crate::{
+    derive::{destructure, determine_idents},
+    interpolation::fields_of,
+};
+
+pub fn generate_impl(derive_input: DeriveInput) -> TokenStream {
+    let DeriveInput {
+        ident,
+        generics,
+        data,
+        ..
+    } = derive_input;
+
+    visit_impl(&ident, &generics, &data)
+}
+
+/// Implements `VisitInterpolations` and `VisitInterpolationsMut` by visiting every field
+///
+/// The code does not depend on the field types, so it works with and without the `macro` feature.
+pub(crate) fn visit_impl(ident: &Ident, generics: &Generics, data: &Data) -> TokenStream {
+    let arms = fields_of(data).into_iter().map(|(variant, fields)| arm(variant, fields, quote!(struct_baker::interpolation::VisitInterpolations), quote!(visit_interpolations)));
+    let arms_mut = fields_of(data).into_iter().map(|(variant, fields)| arm(variant, fields, quote!(struct_baker::interpolation::VisitInterpolationsMut), quote!(visit_interpolations_mut)));
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics struct_baker::interpolation::VisitInterpolations for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn visit_interpolations(
+                &self,
+                path: &mut struct_baker::path::FieldPath,
+                visitor: &mut dyn FnMut(&struct_baker::path::FieldPath, &struct_baker::util::TokenTree),
+            ) {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+
+        impl #impl_generics struct_baker::interpolation::VisitInterpolationsMut for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn visit_interpolations_mut(
+                &mut self,
+                path: &mut struct_baker::path::FieldPath,
+                visitor: &mut dyn FnMut(&struct_baker::path::FieldPath, &mut struct_baker::util::TokenTree),
+            ) {
+                match self {
+                    #(#arms_mut)*
+                }
+            }
+        }
+    }
+}
+
+fn arm(variant: Option<&Ident>, fields: &Fields, trait_path: TokenStream, method: TokenStream) -> TokenStream {
+    let idents = determine_idents(fields);
+    let destructured = destructure(fields);
+    let names = fields.iter().enumerate().map(|(index, field)| match &field.ident {
+        Some(ident) => ident.to_string(),
+        None => index.to_string(),
+    });
+
+    let visit_fields = quote! {
+        #(path.with(struct_baker::path::PathSegment::Field(#names.to_owned()), |path| {
+            #trait_path::#method(#idents, path, visitor)
+        });)*
+    };
+
+    match variant {
+        Some(variant) => {
+            let name = variant.to_string();
+            quote! {
+                Self :: #variant #destructured => path.with(struct_baker::path::PathSegment::Variant(#name.to_owned()), |path| {
+                    #visit_fields
+                }),
+            }
+        }
+        None => quote! {
+            Self #destructured => {
+                #visit_fields
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn visit_fields() {
+        let input: DeriveInput = parse_quote! {
+            enum Node {
+                Leaf(i64),
+                Branch { children: Vec<Node> },
+            }
+        };
+        let expansion = generate_impl(input).to_string();
+
+        let visit = quote!(struct_baker::interpolation::VisitInterpolations for Node);
+        assert!(expansion.contains(&visit.to_string()), "{expansion}");
+        let visit_mut = quote!(struct_baker::interpolation::VisitInterpolationsMut for Node);
+        assert!(expansion.contains(&visit_mut.to_string()), "{expansion}");
+        let leaf = quote!(struct_baker::path::PathSegment::Variant("Leaf".to_owned()));
+        assert!(expansion.contains(&format!("Self :: Leaf (x_0) => path . with ({leaf}")), "{expansion}");
+        let children = quote!(struct_baker::path::PathSegment::Field("children".to_owned()));
+        assert!(expansion.contains(&children.to_string()), "{expansion}");
+        let field = quote!(struct_baker::path::PathSegment::Field("0".to_owned()));
+        assert!(expansion.contains(&field.to_string()), "{expansion}");
+    }
+}
@@ -0,0 +1,537 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, quote};
+
+use crate::BakeContext;
+
+/// The fields of a struct or variant, already baked
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fields<O> {
+    Named(Vec<(String, O)>),
+    Unnamed(Vec<O>),
+    Unit,
+}
+
+impl<O> Fields<O> {
+    pub fn map<P>(self, mut f: impl FnMut(O) -> P) -> Fields<P> {
+        match self {
+            Fields::Named(fields) => Fields::Named(fields.into_iter().map(|(name, o)| (name, f(o))).collect()),
+            Fields::Unnamed(fields) => Fields::Unnamed(fields.into_iter().map(f).collect()),
+            Fields::Unit => Fields::Unit,
+        }
+    }
+}
+
+/// How a sequence is constructed
+#[derive(Debug, Clone)]
+pub enum Seq {
+    /// `[a, b]`
+    Array,
+    /// `vec![a, b]`, the path of the macro is taken from the [BakeContext]
+    Vec,
+    /// `path::from([a, b])` for collections like `std::collections::VecDeque`
+    Collection(TokenStream),
+}
+
+/// Turns the parts of a value into some output, like a serializer
+///
+/// [Bake::bake_into](crate::Bake::bake_into) calls the baker for every part of a value, bottom up.
+/// [TokenBaker] produces the same tokens as `to_stream`, [SourceBaker] pretty printed source code,
+/// [SizeBaker] the number of tokens and [TreeBaker] a [BakeTree] for debugging.
+pub trait Baker {
+    type Output;
+
+    fn context(&self) -> &BakeContext;
+
+    /// A literal like `1u8`, `-1i32`, `'c'`, `"str"` or `true`
+    fn literal(&mut self, literal: TokenStream) -> Self::Output;
+
+    /// An owned `String`
+    fn string(&mut self, value: &str) -> Self::Output;
+
+    /// A struct, a tuple struct or a constructor call like `Some(..)` or `Box::new(..)`
+    fn structure(&mut self, path: TokenStream, fields: Fields<Self::Output>) -> Self::Output;
+
+    /// A variant of the enum at `path`
+    fn variant(&mut self, path: TokenStream, variant: &str, fields: Fields<Self::Output>) -> Self::Output;
+
+    fn tuple(&mut self, elements: Vec<Self::Output>) -> Self::Output;
+
+    fn seq(&mut self, kind: Seq, elements: Vec<Self::Output>) -> Self::Output;
+
+    /// A map like `std::collections::HashMap` built from its entries
+    fn map(&mut self, path: TokenStream, entries: Vec<(Self::Output, Self::Output)>) -> Self::Output;
+
+    /// Tokens of types that do not use the baker, and holes
+    fn tokens(&mut self, tokens: TokenStream) -> Self::Output;
+}
+
+/// Bakes into the same `TokenStream` as `to_stream_with`
+#[derive(Clone, Default)]
+pub struct TokenBaker {
+    context: BakeContext,
+}
+
+impl TokenBaker {
+    pub fn new(context: BakeContext) -> Self {
+        Self { context }
+    }
+}
+
+impl Baker for TokenBaker {
+    type Output = TokenStream;
+
+    fn context(&self) -> &BakeContext {
+        &self.context
+    }
+
+    fn literal(&mut self, literal: TokenStream) -> TokenStream {
+        literal
+    }
+
+    fn string(&mut self, value: &str) -> TokenStream {
+        quote!(#value.to_owned())
+    }
+
+    fn structure(&mut self, path: TokenStream, fields: Fields<TokenStream>) -> TokenStream {
+        let fields = token_fields(fields);
+        quote!(#path #fields)
+    }
+
+    fn variant(&mut self, path: TokenStream, variant: &str, fields: Fields<TokenStream>) -> TokenStream {
+        let variant = format_ident!("{variant}");
+        let fields = token_fields(fields);
+        quote!(#path :: #variant #fields)
+    }
+
+    fn tuple(&mut self, elements: Vec<TokenStream>) -> TokenStream {
+        quote!((#(#elements,)*))
+    }
+
+    fn seq(&mut self, kind: Seq, elements: Vec<TokenStream>) -> TokenStream {
+        match kind {
+            Seq::Array => quote!([#(#elements),*]),
+            Seq::Vec => {
+                let vec = self.context.vec_macro();
+                quote!(#vec[#(#elements),*])
+            }
+            Seq::Collection(path) => quote!(#path::from([#(#elements),*])),
+        }
+    }
+
+    fn map(&mut self, path: TokenStream, entries: Vec<(TokenStream, TokenStream)>) -> TokenStream {
+        let entries = entries.into_iter().map(|(k, v)| quote!((#k, #v)));
+        quote!(#path::from([#(#entries),*]))
+    }
+
+    fn tokens(&mut self, tokens: TokenStream) -> TokenStream {
+        tokens
+    }
+}
+
+fn token_fields(fields: Fields<TokenStream>) -> TokenStream {
+    match fields {
+        Fields::Named(fields) => {
+            let fields = fields.into_iter().map(|(name, value)| {
+                let name = format_ident!("{name}");
+                quote!(#name: #value)
+            });
+            quote!({ #(#fields),* })
+        }
+        Fields::Unnamed(fields) => quote!((#(#fields),*)),
+        Fields::Unit => quote!(),
+    }
+}
+
+/// Bakes into pretty printed rust source code, for example to write generated code to a file
+///
+/// Compound values are broken into one line per element once they get longer than `width`.
+#[derive(Clone)]
+pub struct SourceBaker {
+    context: BakeContext,
+    width: usize,
+}
+
+impl Default for SourceBaker {
+    fn default() -> Self {
+        Self {
+            context: BakeContext::default(),
+            width: 80,
+        }
+    }
+}
+
+impl SourceBaker {
+    pub fn new(context: BakeContext) -> Self {
+        Self {
+            context,
+            ..Self::default()
+        }
+    }
+
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// `items` in `open` and `close` on one line or, if that is too long, on one line each
+    fn group(&self, prefix: &str, (open, close): (char, char), items: Vec<String>, padded: bool) -> String {
+        let pad = if padded && !items.is_empty() { " " } else { "" };
+        let single = format!("{prefix}{open}{pad}{}{pad}{close}", items.join(", "));
+        if items.is_empty() || single.len() <= self.width && !single.contains('\n') {
+            return single;
+        }
+
+        let items: String = items
+            .iter()
+            .map(|item| format!("    {},\n", item.replace('\n', "\n    ")))
+            .collect();
+        format!("{prefix}{open}\n{items}{close}")
+    }
+
+    fn fields(&self, prefix: String, fields: Fields<String>) -> String {
+        match fields {
+            Fields::Named(fields) => {
+                let fields = fields.into_iter().map(|(name, value)| format!("{name}: {value}")).collect();
+                self.group(&format!("{prefix} "), ('{', '}'), fields, true)
+            }
+            Fields::Unnamed(fields) => self.group(&prefix, ('(', ')'), fields, false),
+            Fields::Unit => prefix,
+        }
+    }
+}
+
+impl Baker for SourceBaker {
+    type Output = String;
+
+    fn context(&self) -> &BakeContext {
+        &self.context
+    }
+
+    fn literal(&mut self, literal: TokenStream) -> String {
+        let literal = literal.to_string();
+        match literal.strip_prefix("- ") {
+            Some(negated) => format!("-{negated}"),
+            None => literal,
+        }
+    }
+
+    fn string(&mut self, value: &str) -> String {
+        format!("{value:?}.to_owned()")
+    }
+
+    fn structure(&mut self, path: TokenStream, fields: Fields<String>) -> String {
+        self.fields(source_path(&path), fields)
+    }
+
+    fn variant(&mut self, path: TokenStream, variant: &str, fields: Fields<String>) -> String {
+        self.fields(format!("{}::{variant}", source_path(&path)), fields)
+    }
+
+    fn tuple(&mut self, elements: Vec<String>) -> String {
+        match elements.len() {
+            1 => format!("({},)", elements[0]),
+            _ => self.group("", ('(', ')'), elements, false),
+        }
+    }
+
+    fn seq(&mut self, kind: Seq, elements: Vec<String>) -> String {
+        match kind {
+            Seq::Array => self.group("", ('[', ']'), elements, false),
+            Seq::Vec => self.group(&source_path(&self.context.vec_macro()), ('[', ']'), elements, false),
+            Seq::Collection(path) => {
+                let from = format!("{}::from(", source_path(&path));
+                format!("{})", self.group(&from, ('[', ']'), elements, false))
+            }
+        }
+    }
+
+    fn map(&mut self, path: TokenStream, entries: Vec<(String, String)>) -> String {
+        let entries = entries.into_iter().map(|(k, v)| format!("({k}, {v})")).collect();
+        let from = format!("{}::from(", source_path(&path));
+        format!("{})", self.group(&from, ('[', ']'), entries, false))
+    }
+
+    fn tokens(&mut self, tokens: TokenStream) -> String {
+        tokens.to_string()
+    }
+}
+
+/// A path without the spaces `TokenStream::to_string` puts around `::` and `!`
+fn source_path(path: &TokenStream) -> String {
+    path.to_string().replace(" :: ", "::").replace(":: ", "::").replace(" !", "!")
+}
+
+/// Estimates the size of the baked code as the number of tokens [TokenBaker] produces, a group counts as one token
+#[derive(Clone, Default)]
+pub struct SizeBaker {
+    context: BakeContext,
+}
+
+impl SizeBaker {
+    pub fn new(context: BakeContext) -> Self {
+        Self { context }
+    }
+}
+
+fn count(tokens: &TokenStream) -> usize {
+    tokens
+        .clone()
+        .into_iter()
+        .map(|tree| match tree {
+            TokenTree::Group(group) => 1 + count(&group.stream()),
+            _ => 1,
+        })
+        .sum()
+}
+
+/// The size of a group containing `sizes` separated by commas
+fn separated(sizes: impl IntoIterator<Item = usize>) -> usize {
+    let (len, sum) = sizes.into_iter().fold((0usize, 0), |(len, sum), size| (len + 1, sum + size));
+    1 + sum + len.saturating_sub(1)
+}
+
+impl Baker for SizeBaker {
+    type Output = usize;
+
+    fn context(&self) -> &BakeContext {
+        &self.context
+    }
+
+    fn literal(&mut self, literal: TokenStream) -> usize {
+        count(&literal)
+    }
+
+    fn string(&mut self, _: &str) -> usize {
+        4
+    }
+
+    fn structure(&mut self, path: TokenStream, fields: Fields<usize>) -> usize {
+        count(&path) + field_size(fields)
+    }
+
+    fn variant(&mut self, path: TokenStream, _: &str, fields: Fields<usize>) -> usize {
+        count(&path) + 3 + field_size(fields)
+    }
+
+    fn tuple(&mut self, elements: Vec<usize>) -> usize {
+        separated(elements) + 1
+    }
+
+    fn seq(&mut self, kind: Seq, elements: Vec<usize>) -> usize {
+        let prefix = match kind {
+            Seq::Array => 0,
+            Seq::Vec => count(&self.context.vec_macro()),
+            Seq::Collection(path) => count(&path) + 4,
+        };
+        prefix + separated(elements)
+    }
+
+    fn map(&mut self, path: TokenStream, entries: Vec<(usize, usize)>) -> usize {
+        count(&path) + 4 + separated(entries.into_iter().map(|(k, v)| k + v + 2))
+    }
+
+    fn tokens(&mut self, tokens: TokenStream) -> usize {
+        count(&tokens)
+    }
+}
+
+fn field_size(fields: Fields<usize>) -> usize {
+    match fields {
+        Fields::Named(fields) => separated(fields.into_iter().map(|(_, size)| size + 2)),
+        Fields::Unnamed(fields) => separated(fields),
+        Fields::Unit => 0,
+    }
+}
+
+/// The structure of a baked value, see [TreeBaker]
+#[derive(Debug, Clone, PartialEq)]
+pub enum BakeTree {
+    Literal(String),
+    String(String),
+    Struct {
+        path: String,
+        fields: Fields<BakeTree>,
+    },
+    Variant {
+        path: String,
+        variant: String,
+        fields: Fields<BakeTree>,
+    },
+    Tuple(Vec<BakeTree>),
+    Seq {
+        kind: String,
+        elements: Vec<BakeTree>,
+    },
+    Map {
+        path: String,
+        entries: Vec<(BakeTree, BakeTree)>,
+    },
+    Tokens(String),
+}
+
+/// Bakes into a [BakeTree] that shows how a value is baked, `{:#?}` prints it as a tree
+#[derive(Clone, Default)]
+pub struct TreeBaker {
+    context: BakeContext,
+}
+
+impl TreeBaker {
+    pub fn new(context: BakeContext) -> Self {
+        Self { context }
+    }
+}
+
+impl Baker for TreeBaker {
+    type Output = BakeTree;
+
+    fn context(&self) -> &BakeContext {
+        &self.context
+    }
+
+    fn literal(&mut self, literal: TokenStream) -> BakeTree {
+        BakeTree::Literal(literal.to_string())
+    }
+
+    fn string(&mut self, value: &str) -> BakeTree {
+        BakeTree::String(value.to_owned())
+    }
+
+    fn structure(&mut self, path: TokenStream, fields: Fields<BakeTree>) -> BakeTree {
+        BakeTree::Struct {
+            path: source_path(&path),
+            fields,
+        }
+    }
+
+    fn variant(&mut self, path: TokenStream, variant: &str, fields: Fields<BakeTree>) -> BakeTree {
+        BakeTree::Variant {
+            path: source_path(&path),
+            variant: variant.to_owned(),
+            fields,
+        }
+    }
+
+    fn tuple(&mut self, elements: Vec<BakeTree>) -> BakeTree {
+        BakeTree::Tuple(elements)
+    }
+
+    fn seq(&mut self, kind: Seq, elements: Vec<BakeTree>) -> BakeTree {
+        let kind = match kind {
+            Seq::Array => "array".to_owned(),
+            Seq::Vec => "vec".to_owned(),
+            Seq::Collection(path) => source_path(&path),
+        };
+        BakeTree::Seq { kind, elements }
+    }
+
+    fn map(&mut self, path: TokenStream, entries: Vec<(BakeTree, BakeTree)>) -> BakeTree {
+        BakeTree::Map {
+            path: source_path(&path),
+            entries,
+        }
+    }
+
+    fn tokens(&mut self, tokens: TokenStream) -> BakeTree {
+        BakeTree::Tokens(tokens.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Bake;
+
+    #[derive(Bake)]
+    pub struct Config {
+        name: String,
+        r#type: Kind,
+        retries: Vec<u8>,
+        timeout: Duration,
+    }
+
+    #[derive(Bake)]
+    pub enum Kind {
+        Plain,
+        Limited(u8),
+        r#Named { r#in: bool },
+    }
+
+    fn config() -> Config {
+        Config {
+            name: "a".to_owned(),
+            r#type: Kind::r#Named { r#in: true },
+            retries: vec![1, 2],
+            timeout: Duration::new(1, 0),
+        }
+    }
+
+    #[test]
+    fn tokens() {
+        let mut baker = TokenBaker::default();
+        let baked = config().bake_into(&mut baker).unwrap();
+        assert_eq!(baked.to_string(), config().to_stream().to_string());
+        syn::parse2::<syn::Expr>(baked).unwrap();
+
+        let limited = Kind::Limited(3).bake_into(&mut baker).unwrap();
+        assert_eq!(limited.to_string(), Kind::Limited(3).to_stream().to_string());
+    }
+
+    #[test]
+    fn source() {
+        let source = config().bake_into(&mut SourceBaker::default()).unwrap();
+        syn::parse_str::<syn::Expr>(&source).unwrap();
+        assert!(source.contains("r#type: "), "{source}");
+        assert!(source.contains("::r#Named { r#in: true }"), "{source}");
+        assert!(source.contains("vec![1u8, 2u8]"), "{source}");
+        assert!(source.contains("std::time::Duration::new(1u64, 0u32)"), "{source}");
+
+        let narrow = config().bake_into(&mut SourceBaker::default().width(20)).unwrap();
+        assert!(narrow.lines().count() > 1);
+        syn::parse_str::<syn::Expr>(&narrow).unwrap();
+    }
+
+    #[test]
+    fn size() {
+        let count = |tokens: TokenStream| super::count(&tokens);
+        for kind in [Kind::Plain, Kind::Limited(3), Kind::r#Named { r#in: false }] {
+            assert_eq!(kind.bake_into(&mut SizeBaker::default()).unwrap(), count(kind.to_stream()));
+        }
+        assert_eq!(config().bake_into(&mut SizeBaker::default()).unwrap(), count(config().to_stream()));
+    }
+
+    #[test]
+    fn tree() {
+        let tree = Kind::Limited(3).bake_into(&mut TreeBaker::default()).unwrap();
+        let BakeTree::Variant { variant, fields, .. } = tree else {
+            panic!("{tree:?}");
+        };
+        assert_eq!(variant, "Limited");
+        assert_eq!(fields, Fields::Unnamed(vec![BakeTree::Literal("3u8".to_owned())]));
+
+        let tree = config().bake_into(&mut TreeBaker::default()).unwrap();
+        let BakeTree::Struct { fields: Fields::Named(fields), .. } = tree else {
+            panic!("{tree:?}");
+        };
+        let names: Vec<_> = fields.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["name", "r#type", "retries", "timeout"]);
+        assert_eq!(fields[0].1, BakeTree::String("a".to_owned()));
+        assert!(matches!(&fields[3].1, BakeTree::Struct { path, .. } if path == "std::time::Duration::new"));
+    }
+
+    #[test]
+    fn std_types() {
+        let mut baker = TokenBaker::default();
+        let slice: &[u8] = &[1, 2];
+        assert_eq!(slice.bake_into(&mut baker).unwrap().to_string(), slice.to_stream().to_string());
+        let range = 1u8..=2;
+        assert_eq!(range.bake_into(&mut baker).unwrap().to_string(), range.to_stream().to_string());
+        let cell = std::cell::RefCell::new(1u8);
+        assert_eq!(cell.bake_into(&mut baker).unwrap().to_string(), cell.to_stream().to_string());
+        let once = std::cell::OnceCell::<u8>::new();
+        assert_eq!(once.bake_into(&mut baker).unwrap().to_string(), once.to_stream().to_string());
+        let bound = std::ops::Bound::Included(1u8);
+        assert_eq!(bound.bake_into(&mut baker).unwrap().to_string(), bound.to_stream().to_string());
+    }
+}
@@ -1,7 +1,12 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use crate::Bake;
+use crate::{
+    baker::{Fields, Seq},
+    context::bake_with_context,
+    path::PathSegment,
+    Bake, BakeContext, BakeError, Baker,
+};
 
 macro_rules! impl_literals {
     ($($name:ty)*) => {
@@ -9,6 +14,10 @@ macro_rules! impl_literals {
             fn to_stream(&self) -> TokenStream {
                 quote!(#self)
             }
+
+            fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+                Ok(baker.literal(quote!(#self)))
+            }
         })*
     };
 }
@@ -22,50 +31,132 @@ impl_literals!(
 );
 
 impl<T: Bake> Bake for Option<T> {
-    fn to_stream(&self) -> TokenStream {
-        match self {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        Ok(match self {
             Self::Some(t) => {
-                let inner = t.to_stream();
+                let inner = t.try_to_stream_with(context)?;
                 quote!(Some(#inner))
             }
             None => quote!(None),
-        }
+        })
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        Ok(match self {
+            Some(t) => {
+                let inner = t.bake_into(baker)?;
+                baker.structure(quote!(Some), Fields::Unnamed(vec![inner]))
+            }
+            None => baker.structure(quote!(None), Fields::Unit),
+        })
     }
 }
 
+/// Bakes every element and marks errors with the index of the element
+pub(crate) fn try_elements<'a, T: Bake + 'a>(
+    elements: impl ExactSizeIterator<Item = &'a T>,
+    context: &BakeContext,
+) -> Result<Vec<TokenStream>, BakeError> {
+    context.check_len(elements.len())?;
+    elements
+        .enumerate()
+        .map(|(index, element)| {
+            element
+                .try_to_stream_with(context)
+                .map_err(|e| e.within(PathSegment::Index(index)))
+        })
+        .collect()
+}
+
+/// Like [try_elements] for a [Baker]
+pub(crate) fn bake_elements<'a, T: Bake + 'a, Bk: Baker>(
+    elements: impl ExactSizeIterator<Item = &'a T>,
+    baker: &mut Bk,
+) -> Result<Vec<Bk::Output>, BakeError> {
+    baker.context().check_len(elements.len())?;
+    elements
+        .enumerate()
+        .map(|(index, element)| {
+            element
+                .bake_into(baker)
+                .map_err(|e| e.within(PathSegment::Index(index)))
+        })
+        .collect()
+}
+
 impl<T: Bake> Bake for [T] {
-    fn to_stream(&self) -> TokenStream {
-        let elements= self.iter().map(Bake::to_stream);
-        quote!([#(#elements),*])
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let elements = try_elements(self.iter(), context)?;
+        Ok(quote!([#(#elements),*]))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let elements = bake_elements(self.iter(), baker)?;
+        Ok(baker.seq(Seq::Array, elements))
     }
 }
 
 impl<T: Bake, const S: usize> Bake for [T; S] {
-    fn to_stream(&self) -> TokenStream {
-        let elements= self.iter().map(Bake::to_stream);
-        quote!([#(#elements),*])
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let elements = try_elements(self.iter(), context)?;
+        Ok(quote!([#(#elements),*]))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let elements = bake_elements(self.iter(), baker)?;
+        Ok(baker.seq(Seq::Array, elements))
     }
 }
 
 impl<T: Bake> Bake for Vec<T> {
-    fn to_stream(&self) -> TokenStream {
-        let elements = self.iter().map(Bake::to_stream);
-        quote!(vec![#(#elements),*])
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let elements = try_elements(self.iter(), context)?;
+        let vec = context.vec_macro();
+        Ok(quote!(#vec[#(#elements),*]))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let elements = bake_elements(self.iter(), baker)?;
+        Ok(baker.seq(Seq::Vec, elements))
     }
 }
 
+/// Bakes as `Ok(..)` or `Err(..)`, older versions only baked the inner value and dropped the variant
 impl<T: Bake, E: Bake> Bake for Result<T, E> {
-    fn to_stream(&self) -> TokenStream {
-        match self {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        Ok(match self {
+            Ok(value) => {
+                let value = value.try_to_stream_with(context)?;
+                quote!(Ok(#value))
+            }
+            Err(error) => {
+                let error = error.try_to_stream_with(context)?;
+                quote!(Err(#error))
+            }
+        })
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        Ok(match self {
             Ok(value) => {
-                let value = value.to_stream();
-                quote!(#value)
+                let value = value.bake_into(baker)?;
+                baker.structure(quote!(Ok), Fields::Unnamed(vec![value]))
             }
             Err(error) => {
-                let error = error.to_stream();
-                quote!(#error)
+                let error = error.bake_into(baker)?;
+                baker.structure(quote!(Err), Fields::Unnamed(vec![error]))
             }
-        }
+        })
     }
 }
 
@@ -73,11 +164,24 @@ impl Bake for String {
     fn to_stream(&self) -> TokenStream {
         quote!(#self.to_owned())
     }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        Ok(baker.string(self))
+    }
 }
 
 impl<T: Bake> Bake for Box<T> {
-    fn to_stream(&self) -> TokenStream {
-        let element = self.as_ref().to_stream();
-        quote!(Box::new(#element))
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let element = self.as_ref().try_to_stream_with(context)?;
+        let boxed = context.boxed();
+        Ok(quote!(#boxed::new(#element)))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let element = self.as_ref().bake_into(baker)?;
+        let boxed = baker.context().boxed();
+        Ok(baker.structure(quote!(#boxed::new), Fields::Unnamed(vec![element])))
     }
 }
@@ -3,33 +3,239 @@ use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedL
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use crate::Bake;
+use crate::{
+    baker::Seq,
+    basic_types::{bake_elements, try_elements},
+    context::{bake_with_context, StdPaths},
+    path::PathSegment,
+    Bake, BakeContext, BakeError, Baker,
+};
 
 macro_rules! impl_lists {
     ($($T:ident),*) => {
         $(impl<B: Bake> Bake for $T<B> {
-            fn to_stream(&self) -> TokenStream {
-                let content = self.iter().map(Bake::to_stream);
-                quote!(std::collections::$T::from([#(#content),*]))
+            bake_with_context!();
+
+            fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+                let content = try_elements(self.iter(), context)?;
+                let collections = context.collections();
+                Ok(quote!(#collections::$T::from([#(#content),*])))
+            }
+
+            fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+                let content = bake_elements(self.iter(), baker)?;
+                let collections = baker.context().collections();
+                Ok(baker.seq(Seq::Collection(quote!(#collections::$T)), content))
             }
         })*
     };
 }
 
-impl_lists!(VecDeque, LinkedList, BTreeSet, HashSet, BinaryHeap);
+impl_lists!(VecDeque, LinkedList, BTreeSet, BinaryHeap);
 
-macro_rules! impl_maps {
-    ($($T:ident),*) => {
-        $(impl<K: Bake, V: Bake> Bake for $T<K, V> {
-            fn to_stream(&self) -> TokenStream {
-                let values = self.iter()
-                    .map(|(k, v)| (k.to_stream(), v.to_stream()))
-                    .map(|(k, v)| quote!((#k, #v)));
-                
-                quote!(std::collections::$T::from([#((#values)),*]))
-            }
-        })*
-    };
+impl<B: Bake> Bake for HashSet<B> {
+    bake_with_context!();
+
+    /// Fails for [StdPaths::Alloc] because `alloc` has no `HashSet`
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        if context.paths() == StdPaths::Alloc {
+            return Err(BakeError::new("HashSet is not available in alloc"));
+        }
+        let content = context.order(try_elements(self.iter(), context)?);
+        Ok(quote!(std::collections::HashSet::from([#(#content),*])))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        if baker.context().paths() == StdPaths::Alloc {
+            return Err(BakeError::new("HashSet is not available in alloc"));
+        }
+        let elements = ordered(self.iter().collect(), |e| *e, baker.context())?;
+        let content = bake_elements(elements.into_iter(), baker)?;
+        Ok(baker.seq(Seq::Collection(quote!(std::collections::HashSet)), content))
+    }
+}
+
+/// Sorts `items` by the baked tokens of `key` if the context is deterministic
+fn ordered<'a, I, T: Bake + 'a>(
+    mut items: Vec<I>,
+    key: impl Fn(&I) -> &'a T,
+    context: &BakeContext,
+) -> Result<Vec<I>, BakeError> {
+    if context.is_deterministic() {
+        let mut keyed = items
+            .into_iter()
+            .map(|item| Ok((key(&item).try_to_stream_with(context)?.to_string(), item)))
+            .collect::<Result<Vec<_>, BakeError>>()?;
+        keyed.sort_by(|(a, _), (b, _)| a.cmp(b));
+        items = keyed.into_iter().map(|(_, item)| item).collect();
+    }
+    Ok(items)
 }
 
-impl_maps!(BTreeMap, HashMap);
+/// Bakes all entries as tuples
+///
+/// Errors in a value are marked with the baked key, errors in a key with the position of the entry.
+pub(crate) fn try_entries<'a, K: Bake + 'a, V: Bake + 'a>(
+    entries: impl ExactSizeIterator<Item = (&'a K, &'a V)>,
+    context: &BakeContext,
+) -> Result<Vec<TokenStream>, BakeError> {
+    context.check_len(entries.len())?;
+    entries
+        .enumerate()
+        .map(|(index, (k, v))| {
+            let k = k
+                .try_to_stream_with(context)
+                .map_err(|e| e.within(entry_key(index)))?;
+            let v = v
+                .try_to_stream_with(context)
+                .map_err(|e| e.within(PathSegment::Key(k.to_string())))?;
+            Ok(quote!((#k, #v)))
+        })
+        .collect()
+}
+
+/// Marks errors in the key of the entry at `index`, which can not be shown because it failed to bake
+fn entry_key(index: usize) -> PathSegment {
+    PathSegment::MapKey(format!("#{index}"))
+}
+
+/// Marks errors in the value of an entry with the baked key
+fn value_key<K: Bake>(key: &K, index: usize, context: &BakeContext) -> PathSegment {
+    match key.try_to_stream_with(context) {
+        Ok(key) => PathSegment::Key(key.to_string()),
+        Err(_) => entry_key(index),
+    }
+}
+
+type Entries<Bk> = Vec<(<Bk as Baker>::Output, <Bk as Baker>::Output)>;
+
+/// Like [try_entries] for a [Baker]
+fn bake_entries<'a, K: Bake + 'a, V: Bake + 'a, Bk: Baker>(
+    entries: impl ExactSizeIterator<Item = (&'a K, &'a V)>,
+    baker: &mut Bk,
+) -> Result<Entries<Bk>, BakeError> {
+    baker.context().check_len(entries.len())?;
+    entries
+        .enumerate()
+        .map(|(index, (k, v))| {
+            let baked = k.bake_into(baker).map_err(|e| e.within(entry_key(index)))?;
+            let v = v
+                .bake_into(baker)
+                .map_err(|e| e.within(value_key(k, index, baker.context())))?;
+            Ok((baked, v))
+        })
+        .collect()
+}
+
+impl<K: Bake, V: Bake> Bake for BTreeMap<K, V> {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        try_btree_map(self.iter(), context)
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        bake_btree_map(self.iter(), baker)
+    }
+}
+
+impl<K: Bake, V: Bake> Bake for HashMap<K, V> {
+    bake_with_context!();
+
+    /// Fails for [StdPaths::Alloc] because `alloc` has no `HashMap`
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        try_hash_map(self.iter(), context)
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        bake_hash_map(self.iter(), baker)
+    }
+}
+
+/// Bakes the entries of a `BTreeMap`, shared with the maps of holes
+pub(crate) fn try_btree_map<'a, K: Bake + 'a, V: Bake + 'a>(
+    entries: impl ExactSizeIterator<Item = (&'a K, &'a V)>,
+    context: &BakeContext,
+) -> Result<TokenStream, BakeError> {
+    let values = try_entries(entries, context)?;
+    let collections = context.collections();
+    Ok(quote!(#collections::BTreeMap::from([#(#values),*])))
+}
+
+/// Like [try_btree_map] for a [Baker]
+pub(crate) fn bake_btree_map<'a, K: Bake + 'a, V: Bake + 'a, Bk: Baker>(
+    entries: impl ExactSizeIterator<Item = (&'a K, &'a V)>,
+    baker: &mut Bk,
+) -> Result<Bk::Output, BakeError> {
+    let entries = bake_entries(entries, baker)?;
+    let collections = baker.context().collections();
+    Ok(baker.map(quote!(#collections::BTreeMap), entries))
+}
+
+/// Bakes the entries of a `HashMap`, fails for [StdPaths::Alloc] because `alloc` has no `HashMap`
+pub(crate) fn try_hash_map<'a, K: Bake + 'a, V: Bake + 'a>(
+    entries: impl ExactSizeIterator<Item = (&'a K, &'a V)>,
+    context: &BakeContext,
+) -> Result<TokenStream, BakeError> {
+    if context.paths() == StdPaths::Alloc {
+        return Err(BakeError::new("HashMap is not available in alloc"));
+    }
+    let values = context.order(try_entries(entries, context)?);
+    Ok(quote!(std::collections::HashMap::from([#(#values),*])))
+}
+
+/// Like [try_hash_map] for a [Baker]
+pub(crate) fn bake_hash_map<'a, K: Bake + 'a, V: Bake + 'a, Bk: Baker>(
+    entries: impl ExactSizeIterator<Item = (&'a K, &'a V)>,
+    baker: &mut Bk,
+) -> Result<Bk::Output, BakeError> {
+    if baker.context().paths() == StdPaths::Alloc {
+        return Err(BakeError::new("HashMap is not available in alloc"));
+    }
+    let entries = ordered(entries.collect(), |(k, _)| *k, baker.context())?;
+    let entries = bake_entries(entries.into_iter(), baker)?;
+    Ok(baker.map(quote!(std::collections::HashMap), entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn error_paths() {
+        let context = BakeContext::new().allow_pointers(false);
+        let values = BTreeMap::from([("a".to_owned(), Rc::new(1))]);
+        let error = values.try_to_stream_with(&context).unwrap_err();
+        assert_eq!(error.path().to_string(), "[\"a\" . to_owned ()]");
+
+        let keys = BTreeMap::from([(1, 2), (3, 4)].map(|(k, v)| (Rc::new(k), v)));
+        let error = keys.try_to_stream_with(&context).unwrap_err();
+        assert_eq!(error.path().to_string(), "key(#0)");
+    }
+
+    #[test]
+    fn deterministic() {
+        let map: HashMap<_, _> = (0..20).map(|i| (i, i.to_string())).collect();
+        let context = BakeContext::new().deterministic(true);
+        let baked = map.try_to_stream_with(&context).unwrap().to_string();
+        let reordered: HashMap<_, _> = (0..20).rev().map(|i| (i, i.to_string())).collect();
+        assert_eq!(reordered.try_to_stream_with(&context).unwrap().to_string(), baked);
+
+        let set: HashSet<_> = (0..20).collect();
+        let baked = set.try_to_stream_with(&context).unwrap().to_string();
+        let mut sorted: Vec<_> = (0..20).map(|i| format!("{i}i32")).collect();
+        sorted.sort();
+        assert!(baked.contains(&sorted.join(" , ")), "{baked}");
+    }
+
+    #[test]
+    fn alloc_paths() {
+        let context = BakeContext::new().std_paths(StdPaths::Alloc);
+        let list = VecDeque::from([1u8]);
+        let baked = list.try_to_stream_with(&context).unwrap().to_string();
+        assert!(baked.starts_with("alloc :: collections :: VecDeque"), "{baked}");
+        assert!(HashSet::from([1u8]).try_to_stream_with(&context).is_err());
+    }
+}
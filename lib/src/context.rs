@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Path;
+
+use crate::BakeError;
+
+/// Where collections and other allocating types are taken from in the baked code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StdPaths {
+    /// `std::collections::..`, `vec!` and `Box` from the prelude
+    #[default]
+    Std,
+    /// `alloc::collections::..`, `alloc::vec!` and `alloc::boxed::Box` for `no_std` crates, needs `extern crate alloc`
+    Alloc,
+}
+
+/// Settings for [Bake::to_stream_with](crate::Bake::to_stream_with)
+///
+/// `to_stream` uses the default context, which only allows smart pointers if the `allow_pointers` feature is enabled.
+#[derive(Clone)]
+pub struct BakeContext {
+    allow_pointers: bool,
+    crate_paths: HashMap<String, Path>,
+    deterministic: bool,
+    std_paths: StdPaths,
+    max_elements: Option<usize>,
+}
+
+impl Default for BakeContext {
+    fn default() -> Self {
+        Self {
+            allow_pointers: cfg!(feature = "allow_pointers"),
+            crate_paths: HashMap::new(),
+            deterministic: false,
+            std_paths: StdPaths::Std,
+            max_elements: None,
+        }
+    }
+}
+
+impl BakeContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bake `Rc`, `Arc` and `Cow` as new instances of their content instead of failing
+    pub fn allow_pointers(mut self, allow: bool) -> Self {
+        self.allow_pointers = allow;
+        self
+    }
+
+    /// Derived types from the crate `name` are baked with `path` in front instead of `name`
+    ///
+    /// Useful if the crate is renamed or reexported by the crate that uses the macro.
+    pub fn crate_path(mut self, name: impl Into<String>, path: Path) -> Self {
+        self.crate_paths.insert(name.into(), path);
+        self
+    }
+
+    /// Bake `HashMap`s and `HashSet`s sorted by their baked entries, so the output does not change between builds
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    pub fn std_paths(mut self, paths: StdPaths) -> Self {
+        self.std_paths = paths;
+        self
+    }
+
+    /// Fail for collections with more than `max` elements
+    pub fn max_elements(mut self, max: usize) -> Self {
+        self.max_elements = Some(max);
+        self
+    }
+
+    pub fn pointers_allowed(&self) -> bool {
+        self.allow_pointers
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    pub fn paths(&self) -> StdPaths {
+        self.std_paths
+    }
+
+    /// The path of a module as returned by `module_path!()` with the crate renamed by [crate_path](Self::crate_path)
+    ///
+    /// Fails if `module_path` is not a valid path.
+    pub fn module_path(&self, module_path: &str) -> Result<Path, BakeError> {
+        let parse = |path: &str| {
+            syn::parse_str::<Path>(path).map_err(|e| BakeError::new(format!("`{module_path}` is not a module path: {e}")))
+        };
+        let (name, rest) = module_path.split_once("::").unwrap_or((module_path, ""));
+        match self.crate_paths.get(name) {
+            Some(path) if rest.is_empty() => Ok(path.clone()),
+            Some(path) => {
+                let rest = parse(rest)?;
+                Ok(syn::parse_quote!(#path :: #rest))
+            }
+            None => parse(module_path),
+        }
+    }
+
+    /// The path to `std::collections` or `alloc::collections`
+    pub fn collections(&self) -> TokenStream {
+        match self.std_paths {
+            StdPaths::Std => quote!(std::collections),
+            StdPaths::Alloc => quote!(alloc::collections),
+        }
+    }
+
+    /// The `vec!` macro, qualified if needed
+    pub fn vec_macro(&self) -> TokenStream {
+        match self.std_paths {
+            StdPaths::Std => quote!(vec!),
+            StdPaths::Alloc => quote!(alloc::vec!),
+        }
+    }
+
+    /// The path of `Box`, qualified if needed
+    pub fn boxed(&self) -> TokenStream {
+        match self.std_paths {
+            StdPaths::Std => quote!(Box),
+            StdPaths::Alloc => quote!(alloc::boxed::Box),
+        }
+    }
+
+    /// Fails if a collection with `len` elements is over the limit
+    pub fn check_len(&self, len: usize) -> Result<(), BakeError> {
+        match self.max_elements {
+            Some(max) if len > max => Err(BakeError::new(format!(
+                "Collection has {len} elements, more than the limit of {max}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sorts baked entries if the context is deterministic
+    pub(crate) fn order(&self, mut entries: Vec<TokenStream>) -> Vec<TokenStream> {
+        if self.deterministic {
+            entries.sort_by_cached_key(ToString::to_string);
+        }
+        entries
+    }
+}
+
+/// Implements the context-free methods of [Bake](crate::Bake) with `try_to_stream_with` and the default context
+///
+/// The infallible methods turn an error into a `compile_error!` instead of panicking.
+macro_rules! bake_with_context {
+    () => {
+        fn to_stream(&self) -> proc_macro2::TokenStream {
+            self.to_stream_with(&$crate::BakeContext::default())
+        }
+
+        fn try_to_stream(&self) -> Result<proc_macro2::TokenStream, $crate::BakeError> {
+            self.try_to_stream_with(&$crate::BakeContext::default())
+        }
+
+        fn to_stream_with(&self, context: &$crate::BakeContext) -> proc_macro2::TokenStream {
+            self.try_to_stream_with(context).unwrap_or_else(|e| e.to_compile_error())
+        }
+    };
+}
+
+pub(crate) use bake_with_context;
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use syn::parse_quote;
+
+    use super::*;
+    use crate::Bake;
+
+    #[test]
+    fn module_paths() {
+        let context = BakeContext::new().crate_path("config", parse_quote!(::app::config));
+        let path = |module_path| context.module_path(module_path).map(|path| quote!(#path).to_string());
+        assert_eq!(path("config").unwrap(), quote!(::app::config).to_string());
+        assert_eq!(path("config::servers").unwrap(), quote!(::app::config::servers).to_string());
+        assert_eq!(path("other::servers").unwrap(), quote!(other::servers).to_string());
+        assert!(path("config::1").is_err());
+        assert!(path("").is_err());
+    }
+
+    #[test]
+    fn errors_become_compile_errors() {
+        let context = BakeContext::new().max_elements(1);
+        let baked = vec![1, 2].to_stream_with(&context).to_string();
+        assert!(baked.contains("compile_error"), "{baked}");
+        assert!(vec![1].to_stream_with(&context).to_string().starts_with("vec !"));
+
+        let baked = Rc::new(1).to_stream_with(&BakeContext::new().allow_pointers(false)).to_string();
+        assert!(baked.contains("compile_error"), "{baked}");
+        let baked = Rc::new(1).to_stream_with(&BakeContext::new().allow_pointers(true)).to_string();
+        assert!(!baked.contains("compile_error"), "{baked}");
+    }
+
+    #[test]
+    fn std_paths() {
+        let context = BakeContext::new().std_paths(StdPaths::Alloc);
+        let baked = vec![Box::new(1)].try_to_stream_with(&context).unwrap().to_string();
+        assert!(baked.starts_with("alloc :: vec !") && baked.contains("alloc :: boxed :: Box"), "{baked}");
+    }
+}
@@ -0,0 +1,268 @@
+use std::{cell::RefCell, fmt::Display};
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+
+use crate::interpolation::SourceMap;
+
+thread_local! {
+    static ACTIVE_DIAGNOSTICS: RefCell<Option<Diagnostics>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub span: Span,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Errors become `compile_error!`s, warnings the use of a `#[deprecated]` item at `span`
+    pub fn to_stream(&self) -> TokenStream {
+        let message = &self.message;
+        match self.level {
+            Level::Error => quote_spanned!(self.span=> ::core::compile_error!{#message}),
+            Level::Warning => {
+                let warning = quote_spanned!(self.span=> Warning);
+                quote!(
+                    const _: () = {
+                        #[deprecated(note = #message)]
+                        struct Warning;
+                        #[allow(dead_code)]
+                        fn warning() {
+                            let _ = #warning;
+                        }
+                    };
+                )
+            }
+        }
+    }
+}
+
+/// Collects errors and warnings so a macro can report all of them at once instead of stopping at the first one
+///
+/// Parsers and `Bake` impls that have no access to the collector can report to the one of the current
+/// [capture](Self::capture) with [error] and [warning].
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn error(&mut self, span: Span, message: impl Display) {
+        self.report(Diagnostic {
+            level: Level::Error,
+            span,
+            message: message.to_string(),
+        })
+    }
+
+    pub fn warning(&mut self, span: Span, message: impl Display) {
+        self.report(Diagnostic {
+            level: Level::Warning,
+            span,
+            message: message.to_string(),
+        })
+    }
+
+    /// An error at the token that produced the text at `offset`
+    pub fn error_at(&mut self, map: &SourceMap, offset: usize, message: impl Display) {
+        self.error(map.span_at(offset), message)
+    }
+
+    /// A warning at the token that produced the text at `offset`
+    pub fn warning_at(&mut self, map: &SourceMap, offset: usize, message: impl Display) {
+        self.warning(map.span_at(offset), message)
+    }
+
+    pub fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic)
+    }
+
+    /// Adds every error contained in a (combined) `syn::Error`
+    pub fn push(&mut self, error: syn::Error) {
+        for error in error {
+            self.error(error.span(), error)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.level == Level::Error)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    /// Runs `f` and collects everything reported with [error] and [warning] while it runs
+    pub fn capture<R>(f: impl FnOnce() -> R) -> (R, Diagnostics) {
+        struct Restore(Option<Diagnostics>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let previous = self.0.take();
+                ACTIVE_DIAGNOSTICS.with(|active| *active.borrow_mut() = previous);
+            }
+        }
+
+        let previous = ACTIVE_DIAGNOSTICS.with(|active| active.borrow_mut().replace(Diagnostics::new()));
+        let restore = Restore(previous);
+        let result = f();
+        let collected = ACTIVE_DIAGNOSTICS.with(|active| active.borrow_mut().take());
+        drop(restore);
+        (result, collected.unwrap_or_default())
+    }
+
+    /// Renders the diagnostics in front of `value` as a block expression
+    ///
+    /// Without a value the block ends with the last error, so no further errors about its type follow.
+    pub fn into_expr(self, value: Option<TokenStream>) -> TokenStream {
+        let mut diagnostics: Vec<_> = self.diagnostics.iter().map(Diagnostic::to_stream).collect();
+        let value = match value {
+            Some(value) => value,
+            None => {
+                let last = self.diagnostics.iter().rposition(|d| d.level == Level::Error);
+                match last {
+                    Some(index) => diagnostics.remove(index),
+                    None => quote!(::core::compile_error!{"Expected a value or an error"}),
+                }
+            }
+        };
+        quote!({ #(#diagnostics)* #value })
+    }
+
+    /// Renders the diagnostics next to `items`
+    pub fn into_items(self, items: TokenStream) -> TokenStream {
+        let diagnostics = self.diagnostics.iter().map(Diagnostic::to_stream);
+        quote!(#(#diagnostics)* #items)
+    }
+
+    /// `Ok(value)` if no errors were reported
+    pub fn into_result<T>(self, value: T) -> Result<T, Diagnostics> {
+        match self.has_errors() {
+            true => Err(self),
+            false => Ok(value),
+        }
+    }
+}
+
+impl From<syn::Error> for Diagnostics {
+    fn from(value: syn::Error) -> Self {
+        let mut diagnostics = Self::new();
+        diagnostics.push(value);
+        diagnostics
+    }
+}
+
+impl Extend<Diagnostic> for Diagnostics {
+    fn extend<I: IntoIterator<Item = Diagnostic>>(&mut self, iter: I) {
+        self.diagnostics.extend(iter)
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.diagnostics.into_iter()
+    }
+}
+
+/// Reports an error to the active [capture](Diagnostics::capture), returns `false` if there is none
+///
+/// Parsers that also run outside of a capture, like the parser of a [RuntimeParsed](crate::interpolation::RuntimeParsed),
+/// have to fail on their own if the error was not reported.
+pub fn error(span: Span, message: impl Display) -> bool {
+    ACTIVE_DIAGNOSTICS.with(|active| match active.borrow_mut().as_mut() {
+        Some(diagnostics) => {
+            diagnostics.error(span, message);
+            true
+        }
+        None => false,
+    })
+}
+
+/// Reports a warning to the active [capture](Diagnostics::capture), returns `false` if there is none
+pub fn warning(span: Span, message: impl Display) -> bool {
+    ACTIVE_DIAGNOSTICS.with(|active| match active.borrow_mut().as_mut() {
+        Some(diagnostics) => {
+            diagnostics.warning(span, message);
+            true
+        }
+        None => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use super::*;
+
+    fn messages(diagnostics: &Diagnostics) -> Vec<(Level, &str)> {
+        diagnostics.iter().map(|d| (d.level, d.message.as_str())).collect()
+    }
+
+    #[test]
+    fn nested_captures() {
+        let (inner, outer) = Diagnostics::capture(|| {
+            error(Span::call_site(), "outer");
+            let (_, inner) = Diagnostics::capture(|| warning(Span::call_site(), "inner"));
+            warning(Span::call_site(), "after");
+            inner
+        });
+        assert_eq!(messages(&inner), [(Level::Warning, "inner")]);
+        assert_eq!(messages(&outer), [(Level::Error, "outer"), (Level::Warning, "after")]);
+        assert!(outer.has_errors() && !inner.has_errors());
+    }
+
+    #[test]
+    fn restored_after_panic() {
+        let ((), outer) = Diagnostics::capture(|| {
+            let panicked = catch_unwind(AssertUnwindSafe(|| {
+                Diagnostics::capture(|| {
+                    error(Span::call_site(), "lost");
+                    panic!("parser panicked")
+                })
+            }));
+            assert!(panicked.is_err());
+            error(Span::call_site(), "kept");
+        });
+        assert_eq!(messages(&outer), [(Level::Error, "kept")]);
+    }
+
+    #[test]
+    fn outside_of_capture() {
+        assert!(!error(Span::call_site(), "dropped"));
+        assert!(!warning(Span::call_site(), "dropped"));
+        let (reported, diagnostics) = Diagnostics::capture(|| error(Span::call_site(), "kept"));
+        assert!(reported);
+        assert_eq!(messages(&diagnostics), [(Level::Error, "kept")]);
+    }
+
+    #[test]
+    fn expressions() {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.warning(Span::call_site(), "careful");
+        diagnostics.error(Span::call_site(), "broken");
+        let expr = diagnostics.clone().into_expr(None).to_string();
+        assert!(expr.contains("deprecated") && expr.ends_with("compile_error ! { \"broken\" } }"), "{expr}");
+        assert!(diagnostics.into_result(()).is_err());
+        assert!(Diagnostics::new().into_result(1).is_ok());
+    }
+}
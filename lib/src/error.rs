@@ -0,0 +1,60 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use proc_macro2::{Span, TokenStream};
+
+use crate::path::{FieldPath, PathSegment};
+
+/// A value that can not be baked, together with the path from the baked value to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BakeError {
+    path: FieldPath,
+    message: String,
+}
+
+impl BakeError {
+    pub fn new(message: impl Display) -> Self {
+        Self {
+            path: FieldPath::new(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Marks the error as coming from the part `segment` of the value, called by containers on the way up
+    pub fn within(mut self, segment: PathSegment) -> Self {
+        self.path.push_front(segment);
+        self
+    }
+
+    pub fn path(&self) -> &FieldPath {
+        &self.path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// A `compile_error!` with the message at the call site of the macro
+    pub fn to_compile_error(&self) -> TokenStream {
+        syn::Error::from(self.clone()).to_compile_error()
+    }
+}
+
+impl Display for BakeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "Can not bake `{}`: {}", self.path, self.message),
+        }
+    }
+}
+
+impl Error for BakeError {}
+
+impl From<BakeError> for syn::Error {
+    fn from(value: BakeError) -> Self {
+        syn::Error::new(Span::call_site(), value)
+    }
+}
@@ -1,18 +1,19 @@
 use std::marker::PhantomData;
 
+use proc_macro2::TokenStream;
 use quote::ToTokens;
 use syn::Path;
 
-use crate::Bakeable;
+use crate::{BakeContext, BakeError, Bakeable, TryBake};
 
-/// A wrapper around an FnOnce and the corresponding path
+/// A wrapper around an FnOnce and the corresponding path or closure
 /// 
 /// Meant to be used mainly with Interpolation::map
 pub struct BakeableFnOnce<F, In, Out>
 where
     F: FnOnce(In) -> Out,
 {
-    path: Path,
+    tokens: TokenStream,
     func: F,
     phantom_in: PhantomData<In>,
     phantom_out: PhantomData<Out>
@@ -23,7 +24,21 @@ where
     F: FnOnce(In) -> Out,
 {
     fn bake(&self) -> proc_macro2::TokenStream {
-        self.path.to_token_stream()
+        self.tokens.clone()
+    }
+}
+
+/// Never fails, the tokens are fixed when the function is created
+impl<F, In, Out> TryBake for BakeableFnOnce<F, In, Out>
+where
+    F: FnOnce(In) -> Out,
+{
+    fn try_bake(&self) -> Result<TokenStream, BakeError> {
+        Ok(self.bake())
+    }
+
+    fn try_bake_with(&self, _: &BakeContext) -> Result<TokenStream, BakeError> {
+        Ok(self.bake())
     }
 }
 
@@ -39,9 +54,17 @@ where
     /// 
     /// Use the bake_fn! macro instead.
     pub fn _new(func: F, path: Path) -> Self
+    {
+        Self::_from_tokens(func, path.to_token_stream())
+    }
+
+    /// To ensure consistency between the closure and the function do not call this constructor directly.
+    /// 
+    /// Use the bake_map! macro instead.
+    pub fn _from_tokens(func: F, tokens: TokenStream) -> Self
     {
         BakeableFnOnce {
-            path,
+            tokens,
             func,
             phantom_in: PhantomData,
             phantom_out: PhantomData,
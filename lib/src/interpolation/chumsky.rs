@@ -0,0 +1,108 @@
+//! chumsky parsers for inputs that contain [Interpolatable]s
+
+use ::chumsky::{error::LabelError, extra::ParserExtra, prelude::*, primitive::custom};
+
+use super::{syntax::ScanError, HoleSyntax, Interpolatable, Interpolate};
+
+/// The label of errors for holes that were found where a runtime value is needed
+pub const RUNTIME_VALUE: &str = "a value instead of an interpolation";
+
+/// Parses a hole like `${...}`
+pub fn interpolation<'src, T, E>() -> impl Parser<'src, &'src str, Interpolatable<T>, E> + Clone
+where
+    E: ParserExtra<'src, &'src str>,
+    E::Error: LabelError<'src, &'src str, &'static str>,
+{
+    interpolation_with(HoleSyntax::Dollar)
+}
+
+/// Parses a hole written with `syntax`, see [interpolation]
+pub fn interpolation_with<'src, T, E>(
+    syntax: HoleSyntax,
+) -> impl Parser<'src, &'src str, Interpolatable<T>, E> + Clone
+where
+    E: ParserExtra<'src, &'src str>,
+    E::Error: LabelError<'src, &'src str, &'static str>,
+{
+    custom(move |inp| {
+        let before = inp.cursor();
+        let text: &str = inp.slice_from(&before..);
+
+        let label = match syntax.scan(text) {
+            Ok((rest, content)) => match Interpolatable::parse_hole(content) {
+                Ok(hole) => {
+                    for _ in text[..text.len() - rest.len()].chars() {
+                        inp.skip();
+                    }
+                    return Ok(hole);
+                }
+                Err(_) => "a valid interpolation",
+            },
+            Err(ScanError::Invalid) => "a valid interpolation",
+            Err(ScanError::NoHole) => "an interpolation",
+            Err(ScanError::Unclosed) => "a closed interpolation",
+        };
+
+        let found = inp.peek_maybe();
+        Err(LabelError::expected_found([label], found, inp.span_since(&before)))
+    })
+}
+
+/// Accepts a hole like `${...}` or whatever `parser` accepts
+///
+/// `parser` is only tried if the input does not start with a hole, so a malformed hole is reported
+/// as such instead of as an error of `parser`.
+pub fn interpolatable<'src, T, E, P>(parser: P) -> impl Parser<'src, &'src str, Interpolatable<T>, E> + Clone
+where
+    E: ParserExtra<'src, &'src str>,
+    E::Error: LabelError<'src, &'src str, &'static str>,
+    P: Parser<'src, &'src str, T, E> + Clone,
+{
+    custom(move |inp| {
+        let before = inp.cursor();
+        match HoleSyntax::Dollar.starts_hole(inp.slice_from(&before..)) {
+            true => inp.parse(interpolation()),
+            false => inp.parse(parser.clone().map(Interpolatable::Actual)),
+        }
+    })
+}
+
+/// Fits the result of `parser`, a hole fails with an error labeled [RUNTIME_VALUE] and the text of the hole
+///
+/// Use this in parsers that run outside of macros and can not produce holes.
+pub fn fit_chumsky<'src, I, T, U, E, P>(parser: P) -> impl Parser<'src, I, T, E> + Clone
+where
+    I: Input<'src>,
+    E: ParserExtra<'src, I>,
+    E::Error: LabelError<'src, I, String>,
+    U: Interpolate<T>,
+    P: Parser<'src, I, U, E> + Clone,
+{
+    parser.try_map(|value, span| value.chumsky(span))
+}
+
+#[cfg(test)]
+mod tests {
+    use ::chumsky::{error::Rich, extra};
+
+    use super::*;
+
+    fn number<'src>() -> impl Parser<'src, &'src str, Interpolatable<u32>, extra::Err<Rich<'src, char>>> {
+        interpolatable(text::int(10).from_str().unwrapped())
+    }
+
+    #[test]
+    fn holes() {
+        assert!(matches!(number().parse("${a}").into_result(), Ok(Interpolatable::Inter(_))));
+        assert!(matches!(number().parse("12").into_result(), Ok(Interpolatable::Actual(12))));
+    }
+
+    #[test]
+    fn malformed_holes() {
+        for (text, label) in [("${a", "a closed interpolation"), ("${)}", "a valid interpolation")] {
+            let errors = number().parse(text).into_errors();
+            assert_eq!(errors.len(), 1, "{text}");
+            assert!(errors[0].to_string().contains(label), "{}", errors[0]);
+        }
+    }
+}
@@ -0,0 +1,425 @@
+// Conversions for containers whose elements are interpolated, see `#[interpolate(elements)]`
+
+use std::{
+    collections::{BTreeMap, HashMap, LinkedList, VecDeque},
+    hash::Hash,
+};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::{
+    collections::{bake_btree_map, bake_hash_map, try_btree_map, try_hash_map},
+    context::bake_with_context,
+    Bake, BakeContext, BakeError, Baker, TryBake,
+};
+
+use super::{FitRef, Interpolatable, Interpolate, RuntimeInterpolationError};
+
+/// Whether a field of a template type still contains holes
+pub trait ContainsInterpolation {
+    fn contains_interpolation(&self) -> bool;
+}
+
+impl<T> ContainsInterpolation for Interpolatable<T> {
+    fn contains_interpolation(&self) -> bool {
+        matches!(self, Interpolatable::Inter(_))
+    }
+}
+
+macro_rules! impl_lists {
+    ($($T:ident),*) => {
+        $(impl<B: Bake> Interpolate<$T<B>> for $T<Interpolatable<B>> {
+            fn fit(self) -> Result<$T<B>, RuntimeInterpolationError> {
+                self.into_iter().map(Interpolate::fit).collect()
+            }
+        })*
+    };
+}
+
+impl_lists!(VecDeque, LinkedList);
+
+macro_rules! impl_contains {
+    ($($T:ident),*) => {
+        $(impl<T> ContainsInterpolation for $T<Interpolatable<T>> {
+            fn contains_interpolation(&self) -> bool {
+                self.iter().any(ContainsInterpolation::contains_interpolation)
+            }
+        })*
+    };
+}
+
+impl_contains!(Vec, VecDeque, LinkedList, Option);
+
+impl<T, const S: usize> ContainsInterpolation for [Interpolatable<T>; S] {
+    fn contains_interpolation(&self) -> bool {
+        self.iter().any(ContainsInterpolation::contains_interpolation)
+    }
+}
+
+impl<T> ContainsInterpolation for Box<Interpolatable<T>> {
+    fn contains_interpolation(&self) -> bool {
+        (**self).contains_interpolation()
+    }
+}
+
+macro_rules! impl_maps {
+    ($($T:ident: $($bound:path)*),*) => {
+        $(impl<K: Bake $(+ $bound)*, V: Bake> Interpolate<$T<K, V>> for $T<K, Interpolatable<V>> {
+            fn fit(self) -> Result<$T<K, V>, RuntimeInterpolationError> {
+                self.into_iter().map(|(k, v)| Ok((k, v.fit()?))).collect()
+            }
+        })*
+    };
+}
+
+impl_maps!(BTreeMap: Ord, HashMap: Eq Hash);
+
+impl<K, V> ContainsInterpolation for BTreeMap<K, Interpolatable<V>> {
+    fn contains_interpolation(&self) -> bool {
+        self.values().any(ContainsInterpolation::contains_interpolation)
+    }
+}
+
+impl<K, V, S> ContainsInterpolation for HashMap<K, Interpolatable<V>, S> {
+    fn contains_interpolation(&self) -> bool {
+        self.values().any(ContainsInterpolation::contains_interpolation)
+    }
+}
+
+impl<T: Bake> Interpolate<Vec<T>> for Vec<Interpolatable<T>> {
+    fn fit(self) -> Result<Vec<T>, RuntimeInterpolationError> {
+        self.into_iter().map(Interpolate::fit).collect()
+    }
+}
+
+impl<T: Bake, const S: usize> Interpolate<[T; S]> for [Interpolatable<T>; S] {
+    fn fit(self) -> Result<[T; S], RuntimeInterpolationError> {
+        if let Some(Interpolatable::Inter(tree)) = self.iter().find(|t| matches!(t, Interpolatable::Inter(_))) {
+            return Err(RuntimeInterpolationError(tree.into()));
+        }
+
+        Ok(self.map(|t| match t {
+            Interpolatable::Actual(t) => t,
+            Interpolatable::Inter(_) => unreachable!("checked above"),
+        }))
+    }
+}
+
+impl<T: Bake> Interpolate<Option<T>> for Option<Interpolatable<T>> {
+    fn fit(self) -> Result<Option<T>, RuntimeInterpolationError> {
+        self.map(Interpolate::fit).transpose()
+    }
+}
+
+impl<T: Bake> Interpolate<Box<T>> for Box<Interpolatable<T>> {
+    fn fit(self) -> Result<Box<T>, RuntimeInterpolationError> {
+        Ok(Box::new((*self).fit()?))
+    }
+}
+
+/// Borrows the elements of a container that may or may not be interpolated
+///
+/// Used by the accessors of `#[bake(accessors)]` for fields with `#[interpolate(elements)]`, `R` is the
+/// container with references to the elements, or a single reference for a `Box`.
+pub trait FitElementsRef<'a, R> {
+    fn fit_elements_ref(&'a self) -> Result<R, RuntimeInterpolationError>;
+}
+
+macro_rules! impl_fit_lists {
+    ($($T:ident),*) => {
+        $(impl<'a, T: Bake + 'a> FitElementsRef<'a, $T<&'a T>> for $T<T> {
+            fn fit_elements_ref(&'a self) -> Result<$T<&'a T>, RuntimeInterpolationError> {
+                Ok(self.iter().collect())
+            }
+        }
+
+        impl<'a, T: Bake + 'a> FitElementsRef<'a, $T<&'a T>> for $T<Interpolatable<T>> {
+            fn fit_elements_ref(&'a self) -> Result<$T<&'a T>, RuntimeInterpolationError> {
+                self.iter().map(FitRef::fit_ref).collect()
+            }
+        })*
+    };
+}
+
+impl_fit_lists!(Vec, VecDeque, LinkedList);
+
+impl<'a, T: Bake + 'a, const S: usize> FitElementsRef<'a, [&'a T; S]> for [T; S] {
+    fn fit_elements_ref(&'a self) -> Result<[&'a T; S], RuntimeInterpolationError> {
+        Ok(self.each_ref())
+    }
+}
+
+impl<'a, T: Bake + 'a, const S: usize> FitElementsRef<'a, [&'a T; S]> for [Interpolatable<T>; S] {
+    fn fit_elements_ref(&'a self) -> Result<[&'a T; S], RuntimeInterpolationError> {
+        if let Some(Interpolatable::Inter(tree)) = self.iter().find(|t| matches!(t, Interpolatable::Inter(_))) {
+            return Err(RuntimeInterpolationError(tree.into()));
+        }
+
+        Ok(self.each_ref().map(|t| match t {
+            Interpolatable::Actual(t) => t,
+            Interpolatable::Inter(_) => unreachable!("checked above"),
+        }))
+    }
+}
+
+impl<'a, T: Bake + 'a> FitElementsRef<'a, Option<&'a T>> for Option<T> {
+    fn fit_elements_ref(&'a self) -> Result<Option<&'a T>, RuntimeInterpolationError> {
+        Ok(self.as_ref())
+    }
+}
+
+impl<'a, T: Bake + 'a> FitElementsRef<'a, Option<&'a T>> for Option<Interpolatable<T>> {
+    fn fit_elements_ref(&'a self) -> Result<Option<&'a T>, RuntimeInterpolationError> {
+        self.as_ref().map(FitRef::fit_ref).transpose()
+    }
+}
+
+impl<'a, T: Bake + 'a> FitElementsRef<'a, &'a T> for Box<T> {
+    fn fit_elements_ref(&'a self) -> Result<&'a T, RuntimeInterpolationError> {
+        Ok(self)
+    }
+}
+
+impl<'a, T: Bake + 'a> FitElementsRef<'a, &'a T> for Box<Interpolatable<T>> {
+    fn fit_elements_ref(&'a self) -> Result<&'a T, RuntimeInterpolationError> {
+        (**self).fit_ref()
+    }
+}
+
+impl<'a, K: Ord + 'a, V: Bake + 'a> FitElementsRef<'a, BTreeMap<&'a K, &'a V>> for BTreeMap<K, V> {
+    fn fit_elements_ref(&'a self) -> Result<BTreeMap<&'a K, &'a V>, RuntimeInterpolationError> {
+        Ok(self.iter().collect())
+    }
+}
+
+impl<'a, K: Ord + 'a, V: Bake + 'a> FitElementsRef<'a, BTreeMap<&'a K, &'a V>> for BTreeMap<K, Interpolatable<V>> {
+    fn fit_elements_ref(&'a self) -> Result<BTreeMap<&'a K, &'a V>, RuntimeInterpolationError> {
+        self.iter().map(|(k, v)| Ok((k, v.fit_ref()?))).collect()
+    }
+}
+
+impl<'a, K: Eq + Hash + 'a, V: Bake + 'a, S> FitElementsRef<'a, HashMap<&'a K, &'a V>> for HashMap<K, V, S> {
+    fn fit_elements_ref(&'a self) -> Result<HashMap<&'a K, &'a V>, RuntimeInterpolationError> {
+        Ok(self.iter().collect())
+    }
+}
+
+impl<'a, K: Eq + Hash + 'a, V: Bake + 'a, S> FitElementsRef<'a, HashMap<&'a K, &'a V>> for HashMap<K, Interpolatable<V>, S> {
+    fn fit_elements_ref(&'a self) -> Result<HashMap<&'a K, &'a V>, RuntimeInterpolationError> {
+        self.iter().map(|(k, v)| Ok((k, v.fit_ref()?))).collect()
+    }
+}
+
+/// Bakes a container whose elements are interpolated, holes are converted with `into`
+///
+/// Used by `#[derive(Bake)]` for fields with `#[interpolate(elements)]`, so `${two}` can fill a
+/// `Vec<Json>` with an `i64` just like it would if the whole list was flattened.
+pub trait BakeElements {
+    fn try_bake_elements(&self, context: &BakeContext) -> Result<TokenStream, BakeError>;
+}
+
+/// An element that bakes its hole as `{hole}.into()`
+struct IntoHole<'a, T>(&'a Interpolatable<T>);
+
+impl<T: Bake> Bake for IntoHole<'_, T> {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        match self.0 {
+            Interpolatable::Inter(tree) => Ok(quote!({#tree}.into())),
+            Interpolatable::Actual(t) => t.try_to_stream_with(context),
+        }
+    }
+}
+
+macro_rules! impl_bake_elements {
+    ($($T:ident),*) => {
+        $(impl<T: Bake> BakeElements for $T<Interpolatable<T>> {
+            fn try_bake_elements(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+                self.iter().map(IntoHole).collect::<$T<_>>().try_to_stream_with(context)
+            }
+        })*
+    };
+}
+
+impl_bake_elements!(Vec, VecDeque, LinkedList);
+
+impl<T: Bake, const S: usize> BakeElements for [Interpolatable<T>; S] {
+    fn try_bake_elements(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        self.each_ref().map(IntoHole).try_to_stream_with(context)
+    }
+}
+
+impl<T: Bake> BakeElements for Option<Interpolatable<T>> {
+    fn try_bake_elements(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        self.as_ref().map(IntoHole).try_to_stream_with(context)
+    }
+}
+
+impl<T: Bake> BakeElements for Box<Interpolatable<T>> {
+    fn try_bake_elements(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        Box::new(IntoHole(&**self)).try_to_stream_with(context)
+    }
+}
+
+impl<K: Bake, V: Bake> BakeElements for BTreeMap<K, Interpolatable<V>> {
+    fn try_bake_elements(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let entries: Vec<_> = self.iter().map(|(k, v)| (k, IntoHole(v))).collect();
+        try_btree_map(entries.iter().map(|(k, v)| (*k, v)), context)
+    }
+}
+
+impl<K: Bake, V: Bake> BakeElements for HashMap<K, Interpolatable<V>> {
+    /// Fails for `StdPaths::Alloc` because `alloc` has no `HashMap`
+    fn try_bake_elements(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let entries: Vec<_> = self.iter().map(|(k, v)| (k, IntoHole(v))).collect();
+        try_hash_map(entries.iter().map(|(k, v)| (*k, v)), context)
+    }
+}
+
+/// An element that bakes its hole as-is, like [TryBake] for an `Interpolatable`
+struct AsIs<'a, T>(&'a Interpolatable<T>);
+
+impl<T: Bake> Bake for AsIs<'_, T> {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        self.0.try_bake_with(context)
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        self.0.try_bake_into(baker)
+    }
+}
+
+// `Interpolatable` is not `Bake`, so the containers of holes that fields without `#[interpolate(elements)]`
+// can have are implemented here, their holes are baked as-is.
+
+macro_rules! impl_bake_lists {
+    ($($T:ident),*) => {
+        $(impl<T: Bake> Bake for $T<Interpolatable<T>> {
+            bake_with_context!();
+
+            fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+                self.iter().map(AsIs).collect::<$T<_>>().try_to_stream_with(context)
+            }
+
+            fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+                self.iter().map(AsIs).collect::<$T<_>>().bake_into(baker)
+            }
+        })*
+    };
+}
+
+impl_bake_lists!(Vec, VecDeque, LinkedList);
+
+impl<T: Bake, const S: usize> Bake for [Interpolatable<T>; S] {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        self.each_ref().map(AsIs).try_to_stream_with(context)
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        self.each_ref().map(AsIs).bake_into(baker)
+    }
+}
+
+impl<T: Bake> Bake for Option<Interpolatable<T>> {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        self.as_ref().map(AsIs).try_to_stream_with(context)
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        self.as_ref().map(AsIs).bake_into(baker)
+    }
+}
+
+impl<T: Bake> Bake for Box<Interpolatable<T>> {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        Box::new(AsIs(&**self)).try_to_stream_with(context)
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        Box::new(AsIs(&**self)).bake_into(baker)
+    }
+}
+
+impl<K: Bake, V: Bake> Bake for BTreeMap<K, Interpolatable<V>> {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let entries: Vec<_> = self.iter().map(|(k, v)| (k, AsIs(v))).collect();
+        try_btree_map(entries.iter().map(|(k, v)| (*k, v)), context)
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let entries: Vec<_> = self.iter().map(|(k, v)| (k, AsIs(v))).collect();
+        bake_btree_map(entries.iter().map(|(k, v)| (*k, v)), baker)
+    }
+}
+
+impl<K: Bake, V: Bake> Bake for HashMap<K, Interpolatable<V>> {
+    bake_with_context!();
+
+    /// Fails for `StdPaths::Alloc` because `alloc` has no `HashMap`
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let entries: Vec<_> = self.iter().map(|(k, v)| (k, AsIs(v))).collect();
+        try_hash_map(entries.iter().map(|(k, v)| (*k, v)), context)
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let entries: Vec<_> = self.iter().map(|(k, v)| (k, AsIs(v))).collect();
+        bake_hash_map(entries.iter().map(|(k, v)| (*k, v)), baker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn hole(name: &str) -> Interpolatable<u8> {
+        Interpolatable::Inter(syn::parse_str(&format!("{{{name}}}")).unwrap())
+    }
+
+    #[test]
+    fn element_holes_keep_their_position() {
+        let context = BakeContext::new();
+        let list = vec![Interpolatable::Actual(1u8), hole("a"), Interpolatable::Actual(2), hole("b")];
+        assert_eq!(
+            list.try_bake_elements(&context).unwrap().to_string(),
+            quote!(vec![1u8, {{ a }}.into(), 2u8, {{ b }}.into()]).to_string()
+        );
+        assert_eq!(list.to_stream().to_string(), quote!(vec![1u8, { a }, 2u8, { b }]).to_string());
+
+        let array = [hole("a"), Interpolatable::Actual(1)];
+        assert_eq!(array.try_bake_elements(&context).unwrap().to_string(), quote!([{{ a }}.into(), 1u8]).to_string());
+        let boxed = Box::new(hole("a"));
+        assert_eq!(boxed.try_bake_elements(&context).unwrap().to_string(), quote!(Box::new({{ a }}.into())).to_string());
+    }
+
+    #[test]
+    fn map_values() {
+        let map = BTreeMap::from([(1u8, hole("a")), (2, Interpolatable::Actual(3))]);
+        let baked = map.try_bake_elements(&BakeContext::new()).unwrap().to_string();
+        assert!(baked.ends_with(&quote!(([(1u8, {{ a }}.into()), (2u8, 3u8)])).to_string()), "{baked}");
+        assert!(map.contains_interpolation());
+        assert!(!BTreeMap::from([(1u8, Interpolatable::Actual(2u8))]).contains_interpolation());
+    }
+
+    #[test]
+    fn fit_elements() {
+        let list = vec![Interpolatable::Actual(1u8), Interpolatable::Actual(2)];
+        assert_eq!(list.fit_elements_ref().unwrap(), [&1, &2]);
+        let list = vec![Interpolatable::Actual(1u8), Interpolatable::Inter(parse_quote!({ a }))];
+        assert!(FitElementsRef::<Vec<&u8>>::fit_elements_ref(&list).is_err());
+        assert!(Interpolate::<Vec<u8>>::fit(list).is_err());
+    }
+}
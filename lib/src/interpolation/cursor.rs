@@ -0,0 +1,299 @@
+use proc_macro2::{Delimiter, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use syn::{parse_quote, Lit};
+
+use super::Interpolatable;
+
+/// A cursor over the tokens of a macro input for parsers that work on tokens instead of text
+///
+/// Unlike parsing `input.to_string()` the spacing of the tokens does not matter and every token keeps its span,
+/// so holes become `Interpolatable::Inter` trees that point at the macro input and errors can be reported
+/// at the right location. Holes are written as `#ident`, `#{..}`, `#(..)`, `$ident` or `${..}`.
+///
+/// Groups without delimiters, which `macro_rules!` creates around captured fragments, are entered transparently.
+#[derive(Clone)]
+pub struct TokenCursor {
+    tokens: Vec<TokenTree>,
+    index: usize,
+    end: Span,
+    prefixes: Vec<char>,
+}
+
+impl TokenCursor {
+    pub fn new(stream: TokenStream) -> Self {
+        Self {
+            tokens: flatten(stream),
+            index: 0,
+            end: Span::call_site(),
+            prefixes: vec!['#', '$'],
+        }
+    }
+
+    /// Only these punctuation characters start a hole, `#` and `$` by default
+    pub fn hole_prefixes(mut self, prefixes: &[char]) -> Self {
+        self.prefixes = prefixes.to_vec();
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index >= self.tokens.len()
+    }
+
+    pub fn peek(&self) -> Option<&TokenTree> {
+        self.peek_nth(0)
+    }
+
+    pub fn peek_nth(&self, n: usize) -> Option<&TokenTree> {
+        self.tokens.get(self.index + n)
+    }
+
+    /// The span of the next token or of the end of the input
+    pub fn span(&self) -> Span {
+        self.peek().map_or(self.end, TokenTree::span)
+    }
+
+    /// An error at the next token
+    pub fn error(&self, message: impl std::fmt::Display) -> syn::Error {
+        syn::Error::new(self.span(), message)
+    }
+
+    pub fn peek_ident(&self, name: &str) -> bool {
+        matches!(self.peek(), Some(TokenTree::Ident(ident)) if ident == name)
+    }
+
+    pub fn peek_punct(&self, c: char) -> bool {
+        matches!(self.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == c)
+    }
+
+    pub fn peek_group(&self, delimiter: Delimiter) -> bool {
+        matches!(self.peek(), Some(TokenTree::Group(group)) if group.delimiter() == delimiter)
+    }
+
+    pub fn ident(&mut self) -> syn::Result<Ident> {
+        match self.peek() {
+            Some(TokenTree::Ident(ident)) => {
+                let ident = ident.clone();
+                self.index += 1;
+                Ok(ident)
+            }
+            _ => Err(self.error("Expected an identifier")),
+        }
+    }
+
+    /// Consumes the identifier `name`
+    pub fn keyword(&mut self, name: &str) -> syn::Result<Ident> {
+        match self.peek_ident(name) {
+            true => self.ident(),
+            false => Err(self.error(format!("Expected `{name}`"))),
+        }
+    }
+
+    pub fn literal(&mut self) -> syn::Result<Literal> {
+        match self.peek() {
+            Some(TokenTree::Literal(literal)) => {
+                let literal = literal.clone();
+                self.index += 1;
+                Ok(literal)
+            }
+            _ => Err(self.error("Expected a literal")),
+        }
+    }
+
+    /// A literal parsed into its value, like a `LitStr` or `LitInt`
+    pub fn lit(&mut self) -> syn::Result<Lit> {
+        Ok(Lit::new(self.literal()?))
+    }
+
+    pub fn punct(&mut self, c: char) -> syn::Result<Punct> {
+        match self.peek() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == c => {
+                let punct = punct.clone();
+                self.index += 1;
+                Ok(punct)
+            }
+            _ => Err(self.error(format!("Expected `{c}`"))),
+        }
+    }
+
+    /// Consumes multi character punctuation like `->` or `::`
+    ///
+    /// All but the last character have to be joined to the next one, so `- >` is not `->`.
+    pub fn puncts(&mut self, puncts: &str) -> syn::Result<Vec<Punct>> {
+        let start = self.index;
+        let count = puncts.chars().count();
+        puncts
+            .chars()
+            .enumerate()
+            .map(|(index, c)| {
+                let punct = self.punct(c)?;
+                match index + 1 == count || punct.spacing() == Spacing::Joint {
+                    true => Ok(punct),
+                    false => Err(self.error(format!("Expected `{puncts}`"))),
+                }
+            })
+            .collect::<syn::Result<_>>()
+            .map_err(|_| {
+                self.index = start;
+                self.error(format!("Expected `{puncts}`"))
+            })
+    }
+
+    /// Consumes a group and returns a cursor over its content
+    pub fn group(&mut self, delimiter: Delimiter) -> syn::Result<TokenCursor> {
+        match self.peek() {
+            Some(TokenTree::Group(group)) if group.delimiter() == delimiter => {
+                let group = group.clone();
+                self.index += 1;
+                Ok(Self {
+                    tokens: flatten(group.stream()),
+                    index: 0,
+                    end: group.span_close(),
+                    prefixes: self.prefixes.clone(),
+                })
+            }
+            _ => Err(self.error(match delimiter {
+                Delimiter::Parenthesis => "Expected `(...)`",
+                Delimiter::Brace => "Expected `{...}`",
+                Delimiter::Bracket => "Expected `[...]`",
+                Delimiter::None => "Expected a group",
+            })),
+        }
+    }
+
+    /// Whether the next tokens are a hole
+    pub fn peek_hole(&self) -> bool {
+        match (self.peek(), self.peek_nth(1)) {
+            (Some(TokenTree::Punct(punct)), Some(TokenTree::Ident(_))) => self.prefixes.contains(&punct.as_char()),
+            (Some(TokenTree::Punct(punct)), Some(TokenTree::Group(group))) => {
+                self.prefixes.contains(&punct.as_char())
+                    && matches!(group.delimiter(), Delimiter::Brace | Delimiter::Parenthesis)
+            }
+            _ => false,
+        }
+    }
+
+    /// Consumes a hole if there is one
+    ///
+    /// `$name` becomes the hole `{name}`, blocks are used as-is so they keep their original spans.
+    pub fn hole<T>(&mut self) -> Option<Interpolatable<T>> {
+        if !self.peek_hole() {
+            return None;
+        }
+
+        let hole = match &self.tokens[self.index + 1] {
+            TokenTree::Ident(ident) => Interpolatable::Inter(parse_quote!({#ident})),
+            TokenTree::Group(group) => Interpolatable::Inter(TokenTree::Group(group.clone())),
+            _ => unreachable!("checked by peek_hole"),
+        };
+
+        self.index += 2;
+        Some(hole)
+    }
+
+    /// Accepts a hole or whatever `parser` accepts
+    pub fn interpolatable<T>(
+        &mut self,
+        parser: impl FnOnce(&mut Self) -> syn::Result<T>,
+    ) -> syn::Result<Interpolatable<T>> {
+        match self.hole() {
+            Some(hole) => Ok(hole),
+            None => parser(self).map(Interpolatable::Actual),
+        }
+    }
+
+    /// Fails if there are tokens left
+    pub fn expect_end(&self) -> syn::Result<()> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(self.error("Unexpected token")),
+        }
+    }
+
+    /// All tokens that have not been consumed yet
+    pub fn rest(self) -> TokenStream {
+        self.tokens.into_iter().skip(self.index).collect()
+    }
+}
+
+/// The trees of `stream` with groups without delimiters replaced by their content
+fn flatten(stream: TokenStream) -> Vec<TokenTree> {
+    stream
+        .into_iter()
+        .flat_map(|tree| match tree {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::None => flatten(group.stream()),
+            tree => vec![tree],
+        })
+        .collect()
+}
+
+impl Iterator for TokenCursor {
+    type Item = TokenTree;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.peek().cloned();
+        self.index += next.is_some() as usize;
+        next
+    }
+}
+
+impl From<TokenStream> for TokenCursor {
+    fn from(value: TokenStream) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::Group;
+    use quote::quote;
+
+    use super::*;
+
+    fn cursor(source: &str) -> TokenCursor {
+        TokenCursor::new(source.parse().unwrap())
+    }
+
+    #[test]
+    fn joint_puncts() {
+        let mut joined = cursor("-> x");
+        assert_eq!(joined.puncts("->").unwrap().len(), 2);
+        assert!(joined.peek_ident("x"));
+
+        let mut spaced = cursor("- > x");
+        assert!(spaced.puncts("->").is_err());
+        // Nothing is consumed when the punctuation does not match
+        assert!(spaced.peek_punct('-'));
+        assert_eq!(spaced.puncts("-").unwrap().len(), 1);
+
+        let mut path = cursor(":: x");
+        assert!(path.puncts("::").is_ok());
+        assert!(cursor(": x").puncts("::").is_err());
+    }
+
+    #[test]
+    fn invisible_groups() {
+        let inner = Group::new(Delimiter::None, quote!(b $c));
+        let mut cursor = TokenCursor::new(quote!(a #inner [d]));
+        assert_eq!(cursor.ident().unwrap(), "a");
+        assert_eq!(cursor.ident().unwrap(), "b");
+        let hole = cursor.hole::<i32>().unwrap();
+        assert!(matches!(hole, Interpolatable::Inter(tree) if tree.to_string() == "{ c }"));
+        let mut group = cursor.group(Delimiter::Bracket).unwrap();
+        assert_eq!(group.ident().unwrap(), "d");
+        assert!(group.expect_end().is_ok());
+        assert!(cursor.is_empty());
+    }
+
+    #[test]
+    fn holes() {
+        let mut cursor = cursor("$a #{ b + 1 } #(c) @d 1");
+        let holes: Vec<_> = std::iter::from_fn(|| cursor.hole::<i32>()).collect();
+        assert_eq!(holes.len(), 3);
+        assert!(!cursor.peek_hole());
+
+        let mut cursor = cursor.hole_prefixes(&['@']);
+        assert!(cursor.hole::<i32>().is_some());
+        let number = cursor.interpolatable(|cursor| cursor.lit()).unwrap();
+        assert!(matches!(number, Interpolatable::Actual(Lit::Int(int)) if int.base10_digits() == "1"));
+        assert!(cursor.expect_end().is_ok());
+    }
+}
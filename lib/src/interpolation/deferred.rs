@@ -0,0 +1,112 @@
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use quote::quote;
+use syn::{parse_str, Path};
+
+use super::Interpolatable;
+
+/// What a [RuntimeParsed] hole does if parsing fails at runtime
+#[derive(Debug, Clone)]
+pub enum ParseFailure {
+    /// Panic with the given message
+    Expect(String),
+    /// Propagate the error with `?`
+    ///
+    /// The macro then has to be called inside of a function that returns a compatible `Result`.
+    Propagate,
+}
+
+/// A hole whose content is only known at runtime as text, like `${str: user_input}`
+///
+/// The text is parsed by calling the function at `parser` with a `&str` when the program runs,
+/// so a single macro call can combine structure parsed at compile time with subtrees parsed at runtime.
+/// `parser` must be reachable from the location of the macro call and return a `Result`.
+///
+/// At runtime there is no [capture](crate::diagnostics::Diagnostics::capture), so [diagnostics::error](crate::diagnostics::error)
+/// only returns `false`. A parser that is also used here has to return its errors instead of only reporting them.
+#[derive(Clone)]
+pub struct RuntimeParsed {
+    pub parser: Path,
+    pub input: TokenTree,
+    pub on_failure: ParseFailure,
+}
+
+impl RuntimeParsed {
+    pub fn new(parser: Path, input: TokenTree) -> Self {
+        Self {
+            parser,
+            input,
+            on_failure: ParseFailure::Expect("Parsing interpolated input failed".to_owned()),
+        }
+    }
+
+    /// Parses `input` as the rust expression that produces the text
+    pub fn parse(parser: Path, input: &str) -> syn::Result<Self> {
+        let input: TokenStream = parse_str(input)?;
+        Ok(Self::new(
+            parser,
+            TokenTree::Group(Group::new(Delimiter::Parenthesis, input)),
+        ))
+    }
+
+    pub fn expect(mut self, message: impl Into<String>) -> Self {
+        self.on_failure = ParseFailure::Expect(message.into());
+        self
+    }
+
+    pub fn propagate(mut self) -> Self {
+        self.on_failure = ParseFailure::Propagate;
+        self
+    }
+
+    /// The expression that calls the parser at runtime
+    pub fn expression(&self) -> TokenStream {
+        let RuntimeParsed { parser, input, on_failure } = self;
+        let call = quote!(#parser(::std::convert::AsRef::<str>::as_ref(&#input)));
+
+        match on_failure {
+            ParseFailure::Expect(message) => quote!(#call.expect(#message)),
+            ParseFailure::Propagate => quote!(#call?),
+        }
+    }
+
+    pub fn into_interpolatable<T>(self) -> Interpolatable<T> {
+        let stream = self.expression();
+        Interpolatable::Inter(TokenTree::Group(Group::new(Delimiter::Brace, stream)))
+    }
+}
+
+impl<T> From<RuntimeParsed> for Interpolatable<T> {
+    fn from(value: RuntimeParsed) -> Self {
+        value.into_interpolatable()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn expressions() {
+        let parsed = RuntimeParsed::parse(parse_quote!(crate::parse_json), "user_input").unwrap();
+        let call = quote!(crate::parse_json(::std::convert::AsRef::<str>::as_ref(&(user_input))));
+        let expected = quote!(#call.expect("Parsing interpolated input failed"));
+        assert_eq!(parsed.expression().to_string(), expected.to_string());
+
+        let expect = parsed.clone().expect("bad json");
+        assert_eq!(expect.expression().to_string(), quote!(#call.expect("bad json")).to_string());
+
+        let propagate = parsed.propagate();
+        assert_eq!(propagate.expression().to_string(), quote!(#call?).to_string());
+        let Interpolatable::<u8>::Inter(tree) = propagate.into() else {
+            panic!("expected a hole");
+        };
+        assert_eq!(tree.to_string(), quote!({ #call? }).to_string());
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(RuntimeParsed::parse(parse_quote!(parse), "(").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use proc_macro2::TokenTree;
+use quote::quote;
+use syn::parse_quote;
+
+use crate::{Bake, Bakeable};
+
+use super::Interpolatable;
+
+/// An entry of a collection that may only be present at runtime
+///
+/// Collecting an iterator of `Element<B>` into an `Interpolatable<Collection>` works just like
+/// collecting `Interpolatable<B>`, but optional and conditional elements are evaluated at runtime
+/// inside the generated collection builder.
+#[derive(Debug, Clone)]
+pub enum Element<T> {
+    /// An element that is always part of the collection
+    Present(Interpolatable<T>),
+    /// A rust expression evaluating to an `Option`, the element is dropped if it is `None`
+    Optional(TokenTree),
+    /// An element that is only part of the collection if the condition evaluates to `true`
+    Conditional(TokenTree, Interpolatable<T>),
+}
+
+impl<T> From<Interpolatable<T>> for Element<T> {
+    fn from(value: Interpolatable<T>) -> Self {
+        Self::Present(value)
+    }
+}
+
+impl<K: Bake, V> Element<(K, V)> {
+    /// A map entry with a fixed key whose value is a rust expression evaluating to an `Option`
+    ///
+    /// The entry is dropped if the value is `None`.
+    pub fn optional_entry(key: Interpolatable<K>, value: TokenTree) -> Self {
+        let key = key.bake();
+        Self::Optional(parse_quote!({(#value).map(|__value| (#key, __value.into()))}))
+    }
+}
+
+impl<T: Bake> Element<T> {
+    /// Returns the inner value if the element is always present and not interpolated
+    pub(crate) fn actual(self) -> Result<T, Self> {
+        match self {
+            Element::Present(Interpolatable::Actual(t)) => Ok(t),
+            other => Err(other),
+        }
+    }
+
+    /// A statement that pushes this element into a `Vec` called `__elements`
+    pub(crate) fn push_statement(&self) -> proc_macro2::TokenStream {
+        match self {
+            Element::Present(Interpolatable::Actual(t)) => {
+                let t = t.to_stream();
+                quote!(__elements.push(#t);)
+            }
+            Element::Present(Interpolatable::Inter(tree)) => {
+                quote!(__elements.push(#tree.into());)
+            }
+            Element::Optional(tree) => quote! {
+                if let Some(__element) = #tree {
+                    __elements.push(__element.into());
+                }
+            },
+            Element::Conditional(condition, element) => {
+                let element = element.bake();
+                quote! {
+                    if #condition {
+                        __elements.push({#element}.into());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use quote::ToTokens;
+
+    use super::*;
+
+    fn tokens(value: Interpolatable<Vec<u8>>) -> String {
+        let Interpolatable::Inter(tree) = value else {
+            panic!("expected a hole");
+        };
+        syn::parse2::<syn::Expr>(tree.to_token_stream()).unwrap();
+        tree.to_string()
+    }
+
+    #[test]
+    fn present_elements_stay_actual() {
+        let elements = [Element::from(Interpolatable::Actual(1u8)), Interpolatable::Actual(2).into()];
+        let collected: Interpolatable<Vec<u8>> = elements.into_iter().collect();
+        assert!(matches!(collected, Interpolatable::Actual(v) if v == [1, 2]));
+    }
+
+    #[test]
+    fn optional_and_conditional() {
+        let elements = [
+            Element::from(Interpolatable::Actual(1u8)),
+            Element::Optional(parse_quote!({ maybe })),
+            Element::Conditional(parse_quote!({ verbose }), Interpolatable::Actual(2)),
+            Interpolatable::<u8>::Inter(parse_quote!({ x })).into(),
+        ];
+        let expected = quote!({
+            let mut __elements = Vec::new();
+            __elements.push(1u8);
+            if let Some(__element) = { maybe } {
+                __elements.push(__element.into());
+            }
+            if { verbose } {
+                __elements.push({ 2u8 }.into());
+            }
+            __elements.push({ x }.into());
+            FromIterator::from_iter(__elements)
+        });
+        assert_eq!(tokens(elements.into_iter().collect()), expected.to_string());
+    }
+
+    #[test]
+    fn optional_entries() {
+        let entry = Element::<(String, u8)>::optional_entry(Interpolatable::Actual("debug".to_owned()), parse_quote!({ level }));
+        let collected: Interpolatable<BTreeMap<String, u8>> = [entry].into_iter().collect();
+        let Interpolatable::Inter(tree) = collected else {
+            panic!("expected a hole");
+        };
+        let baked = tree.to_string();
+        let value = quote!(({ level }).map(|__value| ("debug".to_owned(), __value.into()))).to_string();
+        assert!(baked.contains(&value), "{baked}");
+    }
+}
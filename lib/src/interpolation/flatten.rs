@@ -1,8 +1,14 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque},
+    hash::Hash,
+};
+
+use proc_macro2::TokenTree;
+use syn::parse_quote;
 
 use crate::{Bake, Bakeable};
 
-use super::Interpolatable;
+use super::{Element, Interpolatable};
 
 
 pub trait FlattenInterpolation<T> {
@@ -21,12 +27,25 @@ pub trait FlattenInterpolation<T> {
     fn flatten_interpolation(self) -> Interpolatable<T>;
 }
 
+/// Already flat, so a single hole can be used wherever a container of holes is expected
+impl<T> FlattenInterpolation<T> for Interpolatable<T> {
+    fn flatten_interpolation(self) -> Interpolatable<T> {
+        self
+    }
+}
+
 impl<T: Bake> FlattenInterpolation<Vec<T>> for Vec<Interpolatable<T>> {
     fn flatten_interpolation(self) -> Interpolatable<Vec<T>> {
         self.into_iter().collect()        
     }
 }
 
+impl<T: Bake> FlattenInterpolation<Vec<T>> for Vec<Element<T>> {
+    fn flatten_interpolation(self) -> Interpolatable<Vec<T>> {
+        self.into_iter().collect()
+    }
+}
+
 impl<K, V> FlattenInterpolation<HashMap<K, V>> for HashMap<Interpolatable<K>, Interpolatable<V>> 
 where
     K: Bake + Eq + Hash,
@@ -37,6 +56,77 @@ where
     }
 }
 
+impl<K, V> FlattenInterpolation<BTreeMap<K, V>> for BTreeMap<Interpolatable<K>, Interpolatable<V>>
+where
+    K: Bake + Ord,
+    V: Bake,
+{
+    fn flatten_interpolation(self) -> Interpolatable<BTreeMap<K, V>> {
+        self.into_iter().map(|t| t.flatten_interpolation()).collect()
+    }
+}
+
+macro_rules! impl_collections {
+    ($($T:ident: $($bound:path)*),*) => {
+        $(impl<T: Bake $(+ $bound)*> FlattenInterpolation<$T<T>> for $T<Interpolatable<T>> {
+            fn flatten_interpolation(self) -> Interpolatable<$T<T>> {
+                self.into_iter().collect()
+            }
+        })*
+    };
+}
+
+impl_collections!(VecDeque:, LinkedList:, BTreeSet: Ord, HashSet: Eq Hash);
+
+impl<T: Bake> FlattenInterpolation<Option<T>> for Option<Interpolatable<T>> {
+    fn flatten_interpolation(self) -> Interpolatable<Option<T>> {
+        match self {
+            None => Interpolatable::Actual(None),
+            Some(Interpolatable::Actual(t)) => Interpolatable::Actual(Some(t)),
+            Some(Interpolatable::Inter(tree)) => Interpolatable::Inter(parse_quote!({Some(#tree.into())})),
+        }
+    }
+}
+
+impl<T: Bake, E: Bake> FlattenInterpolation<Result<T, E>> for Result<Interpolatable<T>, Interpolatable<E>> {
+    fn flatten_interpolation(self) -> Interpolatable<Result<T, E>> {
+        match self {
+            Ok(Interpolatable::Actual(t)) => Interpolatable::Actual(Ok(t)),
+            Err(Interpolatable::Actual(e)) => Interpolatable::Actual(Err(e)),
+            Ok(Interpolatable::Inter(tree)) => Interpolatable::Inter(parse_quote!({Ok(#tree.into())})),
+            Err(Interpolatable::Inter(tree)) => Interpolatable::Inter(parse_quote!({Err(#tree.into())})),
+        }
+    }
+}
+
+impl<T: Bake> FlattenInterpolation<Box<T>> for Box<Interpolatable<T>> {
+    fn flatten_interpolation(self) -> Interpolatable<Box<T>> {
+        match *self {
+            Interpolatable::Actual(t) => Interpolatable::Actual(Box::new(t)),
+            Interpolatable::Inter(tree) => Interpolatable::Inter(parse_quote!({Box::new(#tree.into())})),
+        }
+    }
+}
+
+impl<T: Bake, const S: usize> FlattenInterpolation<[T; S]> for [Interpolatable<T>; S] {
+    fn flatten_interpolation(self) -> Interpolatable<[T; S]> {
+        if self.iter().all(|t| matches!(t, Interpolatable::Actual(_))) {
+            return Interpolatable::Actual(self.map(|t| match t {
+                Interpolatable::Actual(t) => t,
+                Interpolatable::Inter(_) => unreachable!("checked above"),
+            }));
+        }
+
+        let elements = self.iter().map(|t| -> TokenTree {
+            match t {
+                Interpolatable::Actual(t) => t.to_token_tree(),
+                Interpolatable::Inter(tree) => parse_quote!({#tree.into()}),
+            }
+        });
+        Interpolatable::Inter(parse_quote!({[#(#elements),*]}))
+    }
+}
+
 macro_rules! impl_tuple {
     ($($($T:ident)+),*) => {
         $(
@@ -70,5 +160,106 @@ impl_tuple!(
     A B C D E F G,
     A B C D E F G H,
     A B C D E F G H I,
-    A B C D E F G H I J
+    A B C D E F G H I J,
+    A B C D E F G H I J K,
+    A B C D E F G H I J K L,
+    A B C D E F G H I J K L M,
+    A B C D E F G H I J K L M N,
+    A B C D E F G H I J K L M N O,
+    A B C D E F G H I J K L M N O P
 );
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::*;
+    use crate::interpolation::{FlattenInterpolation, Interpolate};
+
+    #[derive(Bake, Debug, PartialEq)]
+    pub struct Point {
+        x: i64,
+        y: i64,
+    }
+
+    #[derive(FlattenInterpolation)]
+    #[flatten(into(Point))]
+    pub struct PointHoles {
+        x: Interpolatable<i64>,
+        y: i64,
+    }
+
+    #[derive(Bake, FlattenInterpolation)]
+    pub enum Shape {
+        Circle(Interpolatable<i64>),
+        Empty,
+    }
+
+    fn hole<T>() -> Interpolatable<T> {
+        Interpolatable::Inter(parse_quote!({ n }))
+    }
+
+    /// The tokens inside the braces of a flattened hole
+    fn inner<T>(value: Interpolatable<T>) -> String {
+        match value {
+            Interpolatable::Inter(TokenTree::Group(group)) => group.stream().to_string(),
+            _ => panic!("expected a hole"),
+        }
+    }
+
+    #[test]
+    fn into_target() {
+        let actual = PointHoles { x: Interpolatable::Actual(1), y: 2 }.flatten_interpolation();
+        assert_eq!(Interpolate::<Point>::fit(actual).unwrap(), Point { x: 1, y: 2 });
+
+        let baked = inner(PointHoles { x: hole(), y: 2 }.flatten_interpolation());
+        assert!(baked.ends_with(&quote!(tests::Point { x: {{ n }}.into(), y: 2i64 }).to_string()), "{baked}");
+    }
+
+    #[test]
+    fn into_self() {
+        let actual = Shape::Circle(Interpolatable::Actual(1)).flatten_interpolation();
+        assert!(matches!(actual, Interpolatable::Actual(Shape::Circle(Interpolatable::Actual(1)))));
+        assert!(matches!(Shape::Empty.flatten_interpolation(), Interpolatable::Actual(Shape::Empty)));
+
+        let baked = inner(Shape::Circle(hole()).flatten_interpolation());
+        assert!(baked.ends_with(&quote!(tests::Shape::Circle({{ n }}.into())).to_string()), "{baked}");
+    }
+
+    #[test]
+    fn collections() {
+        let actual: Interpolatable<Vec<u8>> = vec![Interpolatable::Actual(1u8)].flatten_interpolation();
+        assert!(matches!(actual, Interpolatable::Actual(v) if v == [1]));
+
+        let list = inner(vec![Interpolatable::Actual(1u8), hole()].flatten_interpolation());
+        assert_eq!(list, quote!(FromIterator::from_iter([{ 1u8 }, {{ n }.into()},])).to_string());
+
+        let set: Interpolatable<BTreeSet<u8>> = BTreeSet::from([Interpolatable::Actual(2u8), Interpolatable::Actual(1)]).flatten_interpolation();
+        assert!(matches!(set, Interpolatable::Actual(s) if s == BTreeSet::from([1, 2])));
+
+        let option: Interpolatable<Option<u8>> = Some(hole()).flatten_interpolation();
+        assert_eq!(inner(option), quote!(Some({ n }.into())).to_string());
+        assert!(matches!(None::<Interpolatable<u8>>.flatten_interpolation(), Interpolatable::Actual(None)));
+
+        let array = [Interpolatable::Actual(1u8), hole()].flatten_interpolation();
+        assert_eq!(inner(array), quote!([{ 1u8 }, {{ n }.into()}]).to_string());
+    }
+
+    #[test]
+    fn tuples() {
+        let pair = (Interpolatable::Actual(1u8), Interpolatable::Actual("a".to_owned())).flatten_interpolation();
+        assert!(matches!(pair, Interpolatable::Actual((1, a)) if a == "a"));
+
+        let pair = (Interpolatable::Actual(1u8), hole::<u8>()).flatten_interpolation();
+        assert_eq!(inner(pair), quote!((1u8.into(), { n }.into(),)).to_string());
+
+        let one = Interpolatable::Actual(1u8);
+        let sixteen = (
+            one.clone(), one.clone(), one.clone(), one.clone(), one.clone(), one.clone(), one.clone(), one.clone(),
+            one.clone(), one.clone(), one.clone(), one.clone(), one.clone(), one.clone(), one.clone(), hole::<u8>(),
+        );
+        let baked = inner(sixteen.flatten_interpolation());
+        assert_eq!(baked.matches(&quote!(1u8.into()).to_string()).count(), 15);
+        assert!(baked.ends_with("{ n } . into () ,)"), "{baked}");
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::ToTokens;
+
+use crate::{context::bake_with_context, Bake, BakeContext, BakeError, Baker};
+
+use super::{Interpolatable, Interpolate, RuntimeInterpolationError};
+
+/// Where a hole starts in the parsed source, lines start at 1 and columns at 0
+///
+/// Only known for holes created from tokens with the `locations` feature, which enables `span-locations` of `proc-macro2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HoleLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A hole stored as text instead of tokens
+///
+/// Unlike a `TokenTree` this is `Send + Sync`, so it can be stored in caches, sent between threads
+/// or used with error types that require it. The tokens are parsed again when they are needed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hole {
+    text: String,
+    location: Option<HoleLocation>,
+}
+
+/// A [Hole] together with the span of the tokens it was created from
+///
+/// Like the span it is not `Send`, so it only lives while a macro is expanded. Converting it into a
+/// `syn::Error` points at the hole, use [into_hole](Self::into_hole) to keep it past the expansion.
+#[derive(Debug, Clone)]
+pub struct SpannedHole {
+    hole: Hole,
+    span: Span,
+}
+
+impl Hole {
+    /// Creates a hole from the text of a single token tree like `{name}`
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            location: None,
+        }
+    }
+
+    pub fn at(mut self, line: usize, column: usize) -> Self {
+        self.location = Some(HoleLocation { line, column });
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn location(&self) -> Option<HoleLocation> {
+        self.location
+    }
+
+    /// Parses the text back into tokens, the result has no meaningful span
+    pub fn to_token_tree(&self) -> syn::Result<TokenTree> {
+        syn::parse_str(&self.text)
+    }
+}
+
+impl From<TokenTree> for Hole {
+    fn from(value: TokenTree) -> Self {
+        Self::from(&value)
+    }
+}
+
+/// Keeps the location of the tree if the `locations` feature is enabled and the compiler provides one
+impl From<&TokenTree> for Hole {
+    fn from(value: &TokenTree) -> Self {
+        Self {
+            text: value.to_string(),
+            location: location(value.span()),
+        }
+    }
+}
+
+#[cfg(feature = "locations")]
+fn location(span: Span) -> Option<HoleLocation> {
+    let start = span.start();
+    // Line 0 means the location is unknown
+    (start.line > 0).then_some(HoleLocation {
+        line: start.line,
+        column: start.column,
+    })
+}
+
+#[cfg(not(feature = "locations"))]
+fn location(_: Span) -> Option<HoleLocation> {
+    None
+}
+
+impl SpannedHole {
+    pub fn hole(&self) -> &Hole {
+        &self.hole
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Drops the span, so the hole can leave the macro
+    pub fn into_hole(self) -> Hole {
+        self.hole
+    }
+}
+
+impl From<&TokenTree> for SpannedHole {
+    fn from(value: &TokenTree) -> Self {
+        Self {
+            hole: value.into(),
+            span: value.span(),
+        }
+    }
+}
+
+impl From<SpannedHole> for Hole {
+    fn from(value: SpannedHole) -> Self {
+        value.hole
+    }
+}
+
+impl Display for SpannedHole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.hole)
+    }
+}
+
+impl From<SpannedHole> for syn::Error {
+    fn from(value: SpannedHole) -> Self {
+        Self::new(value.span, format!("Runtime interpolation of {} is not possible", value.hole))
+    }
+}
+
+impl FromStr for Hole {
+    type Err = syn::Error;
+
+    /// Checks that `s` is a single token tree
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        syn::parse_str::<TokenTree>(s)?;
+        Ok(Self::new(s))
+    }
+}
+
+impl Display for Hole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.location {
+            Some(HoleLocation { line, column }) => write!(f, "{} at {line}:{column}", self.text),
+            None => write!(f, "{}", self.text),
+        }
+    }
+}
+
+/// An [Interpolatable] whose holes are stored as [Hole]s
+///
+/// `Send + Sync` whenever `T` is, create it with [Interpolatable::detach] and turn it back with
+/// [attach](Self::attach) or bake it directly.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Detached<T> {
+    Inter(Hole),
+    Actual(T),
+}
+
+impl<T> Detached<T> {
+    /// Fails if a hole does not contain a valid token tree
+    pub fn attach(self) -> syn::Result<Interpolatable<T>> {
+        match self {
+            Detached::Inter(hole) => Ok(Interpolatable::Inter(hole.to_token_tree()?)),
+            Detached::Actual(t) => Ok(Interpolatable::Actual(t)),
+        }
+    }
+}
+
+impl<T> Interpolatable<T> {
+    /// Stores holes as text so the value can leave the macro, see [Detached]
+    pub fn detach(self) -> Detached<T> {
+        match self {
+            Interpolatable::Inter(tree) => Detached::Inter(tree.into()),
+            Interpolatable::Actual(t) => Detached::Actual(t),
+        }
+    }
+}
+
+impl<T> Interpolatable<T> {
+    /// Like [fit](Interpolate::fit) but the error keeps the span of the hole
+    ///
+    /// Use it inside a macro, so the `syn::Error` created by `?` points at the hole instead of the whole macro.
+    pub fn fit_spanned(self) -> Result<T, SpannedHole> {
+        match self {
+            Interpolatable::Inter(tree) => Err((&tree).into()),
+            Interpolatable::Actual(t) => Ok(t),
+        }
+    }
+}
+
+impl<T> From<Interpolatable<T>> for Detached<T> {
+    fn from(value: Interpolatable<T>) -> Self {
+        value.detach()
+    }
+}
+
+impl<T: Bake> Bake for Detached<T> {
+    bake_with_context!();
+
+    /// Fails if a hole does not contain a valid token tree
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        match self {
+            Detached::Inter(hole) => hole
+                .to_token_tree()
+                .map(ToTokens::into_token_stream)
+                .map_err(|e| BakeError::new(format!("Detached hole is not a valid token tree: {e}"))),
+            Detached::Actual(t) => t.try_to_stream_with(context),
+        }
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        match self {
+            Detached::Inter(_) => {
+                let tokens = self.try_to_stream_with(baker.context())?;
+                Ok(baker.tokens(tokens))
+            }
+            Detached::Actual(t) => t.bake_into(baker),
+        }
+    }
+}
+
+impl<T: Bake> Interpolate<T> for Detached<T> {
+    fn fit(self) -> Result<T, RuntimeInterpolationError> {
+        match self {
+            Detached::Inter(hole) => Err(RuntimeInterpolationError(hole)),
+            Detached::Actual(t) => Ok(t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::TokenTree;
+    use quote::quote;
+
+    use super::*;
+    use crate::TryBake;
+
+    fn tree() -> TokenTree {
+        syn::parse2(quote!({ name })).unwrap()
+    }
+
+    #[test]
+    fn holes_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Hole>();
+        assert_send_sync::<Detached<i32>>();
+        assert_send_sync::<RuntimeInterpolationError>();
+    }
+
+    #[test]
+    fn location() {
+        let hole = Hole::from(tree());
+        assert_eq!(hole.text(), tree().to_string());
+        // Outside of a macro proc-macro2 only tracks locations with `span-locations`
+        assert_eq!(hole.location().is_some(), cfg!(feature = "locations"));
+        assert_eq!(Hole::new("{a}").at(2, 4).to_string(), "{a} at 2:4");
+    }
+
+    #[test]
+    fn attach() {
+        let detached = Interpolatable::<i32>::Inter(tree()).detach();
+        assert_eq!(detached, Detached::Inter(Hole::from(tree())));
+        let attached = detached.attach().unwrap();
+        assert_eq!(attached.try_bake().unwrap().to_string(), tree().to_string());
+        assert!(Detached::<i32>::Inter(Hole::new("{")).attach().is_err());
+    }
+
+    #[test]
+    fn fit_spanned() {
+        let error = Interpolatable::<i32>::Inter(tree()).fit_spanned().unwrap_err();
+        assert_eq!(error.hole().text(), tree().to_string());
+        assert!(syn::Error::from(error).to_string().contains("name"));
+        assert_eq!(Interpolatable::Actual(1).fit_spanned().unwrap(), 1);
+    }
+}
@@ -1,21 +1,52 @@
 use std::{
     error::Error,
-    fmt,
-    fmt::{Display, Formatter},
+    fmt::{self, Debug, Display, Formatter},
     ops::Deref,
 };
 
-use proc_macro2::{TokenStream, TokenTree};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
 use quote::{quote, ToTokens};
-use syn::{parse2, parse_quote};
+use syn::parse_quote;
 
-use crate::{functions::BakeableFnOnce, Bake, Bakeable};
+use crate::{functions::BakeableFnOnce, Bake, BakeContext, BakeError, Bakeable, Baker, TryBake};
 
 pub mod helper;
+pub mod hole;
 pub mod ops;
+pub mod cursor;
+pub mod deferred;
+pub mod render;
+pub mod source_map;
+pub mod string;
+pub mod syntax;
+pub mod template;
+pub mod visit;
+#[cfg(feature = "nom")]
+pub mod nom;
+#[cfg(feature = "winnow")]
+pub mod winnow;
+#[cfg(feature = "pest")]
+pub mod pest;
+#[cfg(feature = "chumsky")]
+pub mod chumsky;
+mod containers;
+mod element;
 mod flatten;
+mod traits;
 
+pub use containers::{BakeElements, ContainsInterpolation, FitElementsRef};
+pub use element::Element;
+pub use hole::{Detached, Hole, HoleLocation, SpannedHole};
 pub use flatten::*;
+pub use struct_baker_derive::FlattenInterpolation;
+pub use cursor::TokenCursor;
+pub use deferred::{ParseFailure, RuntimeParsed};
+pub use source_map::SourceMap;
+pub use render::{FillHoles, RenderContext, RenderError, Template};
+pub use string::{InterpolatedString, StringSegment};
+pub use syntax::{interpolatable, FromScanError, HoleSyntax, ScanError};
+pub use visit::{VisitInterpolations, VisitInterpolationsMut};
+pub use template::{bake_template, parse_template, TemplateParameter, TemplateSignature};
 
 #[derive(Debug, Clone)]
 pub enum Interpolatable<T> {
@@ -35,22 +66,76 @@ impl<T> Interpolatable<T> {
     pub fn new_inter_raw(tree: TokenTree) -> Self {
         Self::Inter(parse_quote!(#tree.into()))
     }
+
+    /// Converts an `Actual` with `into`, holes are kept as they are
+    ///
+    /// Used to turn an `Interpolatable` of a template type into one of the type it is baked as.
+    pub fn cast<U>(self) -> Interpolatable<U>
+    where
+        T: Into<Interpolatable<U>>,
+    {
+        match self {
+            Interpolatable::Inter(tree) => Interpolatable::Inter(tree),
+            Interpolatable::Actual(t) => t.into(),
+        }
+    }
 }
 
-// create `new` method that wraps the stream in a tree and adds .into()
-// create secondary new without into for from iterator
+impl<T> Interpolatable<T> {
+    /// Creates a hole that refers to a variable or template parameter called `name`
+    ///
+    /// The variable is converted with `into`, so a `&str` parameter can fill a `String`.
+    pub fn named_hole(name: Ident) -> Self {
+        Self::Inter(parse_quote!({#name.into()}))
+    }
 
-impl<T: Bake + PartialEq> PartialEq for Interpolatable<T> {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::Actual(a), Self::Actual(b)) => a.eq(b),
-            _ => false,
+    /// Parses the content of a hole like `${...}` and interpolates it as-is
+    ///
+    /// Like any other hole it is converted with `into` when it is flattened into a container.
+    pub fn parse_hole(content: &str) -> syn::Result<Self> {
+        Ok(Self::Inter(syn::parse_str(&format!("{{{content}}}"))?))
+    }
+}
+
+impl<T: 'static> Interpolatable<T> {
+    /// Like [named_hole](Self::named_hole) but filled from `context` if it has a value called `name`
+    ///
+    /// For parsers that flatten their holes right away and can not be used with a [Template].
+    /// Fails if the value in the context is not a `T`.
+    pub fn named_hole_in(name: Ident, context: &render::RenderContext) -> syn::Result<Self> {
+        match context.get(&name.to_string()) {
+            Ok(Some(value)) => Ok(Self::Actual(value)),
+            Ok(None) => Ok(Self::named_hole(name)),
+            Err(e) => Err(syn::Error::new(name.span(), e)),
+        }
+    }
+
+    /// Like [parse_hole](Self::parse_hole) but a single identifier is filled from `context` if it has a value
+    pub fn parse_hole_in(content: &str, context: &render::RenderContext) -> syn::Result<Self> {
+        let name = match syn::parse_str::<Ident>(content.trim()) {
+            Ok(name) => name,
+            Err(_) => return Self::parse_hole(content),
+        };
+        match context.get(&name.to_string()) {
+            Ok(Some(value)) => Ok(Self::Actual(value)),
+            Ok(None) => Self::parse_hole(content),
+            Err(e) => Err(syn::Error::new(name.span(), e)),
         }
     }
 }
 
+// create `new` method that wraps the stream in a tree and adds .into()
+// create secondary new without into for from iterator
+
 #[derive(Debug)]
-pub struct RuntimeInterpolationError(TokenTree);
+pub struct RuntimeInterpolationError(Hole);
+
+impl RuntimeInterpolationError {
+    /// The hole that could not be converted
+    pub fn hole(&self) -> &Hole {
+        &self.0
+    }
+}
 
 impl Display for RuntimeInterpolationError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -60,10 +145,10 @@ impl Display for RuntimeInterpolationError {
 
 impl Error for RuntimeInterpolationError {}
 
-trait UnwrapInterpolation<T> {
-    fn unwrap(self) -> T;
-}
-
+/// Holes are baked as-is
+///
+/// `Interpolatable` does not implement `Bake`, so `fit()` on it is not ambiguous with the blanket impls of
+/// [Interpolate] for every `T: Bake`. Use [TryBake] to bake it in a manual `Bake` impl.
 impl<T: Bake> Bakeable for Interpolatable<T> {
     fn bake(&self) -> TokenStream {
         match self {
@@ -71,6 +156,36 @@ impl<T: Bake> Bakeable for Interpolatable<T> {
             Interpolatable::Actual(t) => t.to_stream(),
         }
     }
+
+    fn bake_with(&self, context: &BakeContext) -> TokenStream {
+        match self {
+            Interpolatable::Inter(tree) => tree.to_token_stream(),
+            Interpolatable::Actual(t) => t.to_stream_with(context),
+        }
+    }
+}
+
+impl<T: Bake> TryBake for Interpolatable<T> {
+    fn try_bake(&self) -> Result<TokenStream, BakeError> {
+        match self {
+            Interpolatable::Inter(tree) => Ok(tree.to_token_stream()),
+            Interpolatable::Actual(t) => t.try_to_stream(),
+        }
+    }
+
+    fn try_bake_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        match self {
+            Interpolatable::Inter(tree) => Ok(tree.to_token_stream()),
+            Interpolatable::Actual(t) => t.try_to_stream_with(context),
+        }
+    }
+
+    fn try_bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        match self {
+            Interpolatable::Inter(tree) => Ok(baker.tokens(tree.to_token_stream())),
+            Interpolatable::Actual(t) => t.bake_into(baker),
+        }
+    }
 }
 
 pub trait Interpolate<T> {
@@ -86,12 +201,69 @@ pub trait Interpolate<T> {
 
     #[cfg(feature = "nom")]
     /// Performs [fit] but will map [RuntimeInterpolationError] to nom::Err::Failure
-    fn nom<I>(self, input: I) -> Result<T, nom::Err<nom::error::Error<I>>>
+    ///
+    /// Works with every error type that can store the [RuntimeInterpolationError], like `nom::error::Error`
+    /// or `VerboseError`.
+    fn nom<I, E>(self, input: I) -> Result<T, ::nom::Err<E>>
     where
         Self: Sized,
+        E: ::nom::error::ParseError<I> + ::nom::error::FromExternalError<I, RuntimeInterpolationError>,
     {
-        self.fit().map_err(|_| {
-            nom::Err::Failure(nom::error::Error::new(input, nom::error::ErrorKind::Fail))
+        self.fit().map_err(|e| {
+            ::nom::Err::Failure(E::from_external_error(input, ::nom::error::ErrorKind::Fail, e))
+        })
+    }
+
+    #[cfg(feature = "nom")]
+    /// Performs [nom](Interpolate::nom) and adds `context` to the error
+    fn nom_context<I: Clone, E>(self, input: I, context: &'static str) -> Result<T, ::nom::Err<E>>
+    where
+        Self: Sized,
+        E: ::nom::error::ContextError<I>
+            + ::nom::error::ParseError<I>
+            + ::nom::error::FromExternalError<I, RuntimeInterpolationError>,
+    {
+        self.nom(input.clone())
+            .map_err(|e| e.map(|e| E::add_context(input, context, e)))
+    }
+
+    #[cfg(feature = "winnow")]
+    /// Performs [fit](Interpolate::fit) but will map [RuntimeInterpolationError] to a winnow error at `input`
+    fn winnow<I, E>(self, input: &I) -> Result<T, E>
+    where
+        Self: Sized,
+        E: ::winnow::error::FromExternalError<I, RuntimeInterpolationError>,
+    {
+        self.fit().map_err(|e| E::from_external_error(input, e))
+    }
+
+    #[cfg(feature = "pest")]
+    /// Performs [fit](Interpolate::fit) but will map [RuntimeInterpolationError] to a pest error at `span`
+    fn pest<R: ::pest::RuleType>(self, span: ::pest::Span) -> Result<T, ::pest::error::Error<R>>
+    where
+        Self: Sized,
+    {
+        self.fit().map_err(|e| {
+            ::pest::error::Error::new_from_span(
+                ::pest::error::ErrorVariant::CustomError { message: e.to_string() },
+                span,
+            )
+        })
+    }
+
+    #[cfg(feature = "chumsky")]
+    /// Performs [fit](Interpolate::fit) but will map [RuntimeInterpolationError] to a chumsky error at `span`
+    ///
+    /// The error is labeled with [RUNTIME_VALUE](crate::interpolation::chumsky::RUNTIME_VALUE) and the hole.
+    fn chumsky<'src, I, E>(self, span: I::Span) -> Result<T, E>
+    where
+        Self: Sized,
+        I: ::chumsky::input::Input<'src>,
+        E: ::chumsky::error::LabelError<'src, I, String>,
+    {
+        self.fit().map_err(|e| {
+            let label = format!("{} `{}`", crate::interpolation::chumsky::RUNTIME_VALUE, e.hole().text());
+            E::expected_found([label], None, span)
         })
     }
 }
@@ -100,7 +272,7 @@ impl<T: Bake> Interpolate<T> for Interpolatable<T> {
     fn fit(self) -> Result<T, RuntimeInterpolationError> {
         match self {
             Interpolatable::Actual(t) => Ok(t),
-            Interpolatable::Inter(tree) => Err(RuntimeInterpolationError(tree)),
+            Interpolatable::Inter(tree) => Err(RuntimeInterpolationError(tree.into())),
         }
     }
 }
@@ -109,7 +281,7 @@ impl<'a, T: Bake> Interpolate<&'a T> for &'a Interpolatable<T> {
     fn fit(self) -> Result<&'a T, RuntimeInterpolationError> {
         match self {
             Interpolatable::Actual(t) => Ok(t),
-            Interpolatable::Inter(tree) => Err(RuntimeInterpolationError(tree.clone())),
+            Interpolatable::Inter(tree) => Err(RuntimeInterpolationError(tree.into())),
         }
     }
 }
@@ -132,6 +304,25 @@ impl<T: Bake> Interpolate<T> for T {
     }
 }
 
+/// Borrows a value that may or may not be interpolatable
+///
+/// Used by the accessors of `#[bake(accessors)]`, so they work with and without the `macro` feature.
+pub trait FitRef<T> {
+    fn fit_ref(&self) -> Result<&T, RuntimeInterpolationError>;
+}
+
+impl<T: Bake> FitRef<T> for Interpolatable<T> {
+    fn fit_ref(&self) -> Result<&T, RuntimeInterpolationError> {
+        self.fit()
+    }
+}
+
+impl<T: Bake> FitRef<T> for T {
+    fn fit_ref(&self) -> Result<&T, RuntimeInterpolationError> {
+        Ok(self)
+    }
+}
+
 impl<T: Bake> From<T> for Interpolatable<T> {
     fn from(value: T) -> Self {
         Self::Actual(value)
@@ -146,7 +337,42 @@ impl<T> From<TokenTree> for Interpolatable<T> {
 
 impl From<RuntimeInterpolationError> for syn::Error {
     fn from(value: RuntimeInterpolationError) -> Self {
-        Self::new_spanned(value.0, "Runtime interpolation is not possible")
+        Self::new(Span::call_site(), format!("Runtime interpolation of {} is not possible", value.0))
+    }
+}
+
+/// Returned by the variant accessors of `#[bake(accessors)]`
+#[derive(Debug)]
+pub enum AccessError {
+    /// The value is another variant
+    Variant { expected: &'static str },
+    /// A field of the variant is a hole
+    Interpolation(RuntimeInterpolationError),
+}
+
+impl Display for AccessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessError::Variant { expected } => write!(f, "Expected the variant `{expected}`"),
+            AccessError::Interpolation(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for AccessError {}
+
+impl From<RuntimeInterpolationError> for AccessError {
+    fn from(value: RuntimeInterpolationError) -> Self {
+        Self::Interpolation(value)
+    }
+}
+
+impl From<AccessError> for syn::Error {
+    fn from(value: AccessError) -> Self {
+        match value {
+            AccessError::Interpolation(e) => e.into(),
+            e => Self::new(Span::call_site(), e),
+        }
     }
 }
 
@@ -236,6 +462,42 @@ impl<B: Bake, Collection: FromIterator<B>> FromIterator<Interpolatable<B>>
     }
 }
 
+impl<B: Bake, Collection: FromIterator<B>> FromIterator<Element<B>>
+    for Interpolatable<Collection>
+{
+    fn from_iter<T: IntoIterator<Item = Element<B>>>(iter: T) -> Self {
+        let mut iter = iter.into_iter();
+        let mut visited: Vec<B> = Vec::with_capacity(iter.size_hint().1.unwrap_or_default());
+        let mut statements: Vec<TokenStream> = Vec::new();
+
+        for element in iter.by_ref() {
+            match element.actual() {
+                Ok(item) => visited.push(item),
+                Err(element) => {
+                    statements.extend(visited.iter().map(|b| {
+                        let b = b.to_stream();
+                        quote!(__elements.push(#b);)
+                    }));
+                    statements.push(element.push_statement());
+                    break;
+                }
+            }
+        }
+
+        if statements.is_empty() {
+            return Interpolatable::Actual(FromIterator::from_iter(visited));
+        }
+
+        statements.extend(iter.map(|element| element.push_statement()));
+
+        Interpolatable::Inter(parse_quote!({
+            let mut __elements = Vec::new();
+            #(#statements)*
+            FromIterator::from_iter(__elements)
+        }))
+    }
+}
+
 impl<T: Bake> Interpolatable<T> {
     /// Maps an `Interpolatable<T>` to `Interpolatable<U>` by applying a function to its contents
     ///
@@ -250,11 +512,71 @@ impl<T: Bake> Interpolatable<T> {
             Actual(inner) => Actual(f.call(inner)),
             Inter(tree) => {
                 let function_path = f.bake();
-                Inter(parse2(quote!(#function_path(#tree.into()))).unwrap())
+                Inter(parse_quote!({#function_path(#tree.into())}))
+            }
+        }
+    }
+
+    /// Like [map](Self::map) but the function may fail
+    ///
+    /// For an `Interpolation` the error can only occur at runtime, the baked code then panics.
+    pub fn and_then<F, U: Bake, E: Debug>(
+        self,
+        f: BakeableFnOnce<F, T, Result<U, E>>,
+    ) -> Result<Interpolatable<U>, E>
+    where
+        F: FnOnce(T) -> Result<U, E>,
+    {
+        use Interpolatable::*;
+        match self {
+            Actual(inner) => f.call(inner).map(Actual),
+            Inter(tree) => {
+                let function_path = f.bake();
+                Ok(Inter(parse_quote!({#function_path(#tree.into()).unwrap()})))
+            }
+        }
+    }
+
+    /// Combines two `Interpolatable`s into a tuple that is only `Actual` if both are
+    pub fn zip<U: Bake>(self, other: Interpolatable<U>) -> Interpolatable<(T, U)> {
+        use Interpolatable::*;
+        match (self, other) {
+            (Actual(a), Actual(b)) => Actual((a, b)),
+            (a, b) => {
+                let (a, b) = (a.argument(), b.argument());
+                Inter(parse_quote!({(#a, #b)}))
+            }
+        }
+    }
+
+    /// Combines two `Interpolatable`s with a function that takes both values as a tuple, like `|(a, b)| a + b`
+    pub fn map2<F, U: Bake, V: Bake>(
+        self,
+        other: Interpolatable<U>,
+        f: BakeableFnOnce<F, (T, U), V>,
+    ) -> Interpolatable<V>
+    where
+        F: FnOnce((T, U)) -> V,
+    {
+        use Interpolatable::*;
+        match (self, other) {
+            (Actual(a), Actual(b)) => Actual(f.call((a, b))),
+            (a, b) => {
+                let function_path = f.bake();
+                let (a, b) = (a.argument(), b.argument());
+                Inter(parse_quote!({#function_path((#a, #b))}))
             }
         }
     }
 
+    /// The value as an argument of a function call, holes are converted with `into`
+    fn argument(&self) -> TokenStream {
+        match self {
+            Interpolatable::Inter(tree) => quote!(#tree.into()),
+            Interpolatable::Actual(t) => t.to_stream(),
+        }
+    }
+
     pub fn actual(self) -> Option<T> {
         match self {
             Interpolatable::Inter(_) => None,
@@ -269,3 +591,102 @@ impl<T: Bake> Interpolatable<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bake;
+
+    #[derive(Bake, Debug, PartialEq)]
+    pub enum Json {
+        Number(i64),
+        List(Vec<Json>),
+    }
+
+    /// `Interpolatable` is not `Bake`, so only one impl of `Interpolate` fits each of these
+    #[test]
+    fn fit_infers() -> Result<(), RuntimeInterpolationError> {
+        let v: Vec<Json> = vec![Json::Number(1)];
+        let list = Json::List(v.fit()?);
+        assert_eq!(list, Json::List(vec![Json::Number(1)]));
+
+        let number = Interpolatable::Actual(2);
+        assert_eq!(Json::Number(number.fit()?), Json::Number(2));
+
+        let holes = vec![Interpolatable::Actual(Json::Number(3))];
+        let same: Vec<Interpolatable<Json>> = holes.fit()?;
+        let elements: Vec<Json> = same.fit()?;
+        assert_eq!(elements, vec![Json::Number(3)]);
+
+        let hole: Interpolatable<Json> = Interpolatable::Inter(parse_quote!({ json }));
+        let kept: Interpolatable<Json> = hole.fit()?;
+        assert!(Interpolate::<Json>::fit(kept).is_err());
+        Ok(())
+    }
+
+    #[derive(Bake, Debug, PartialEq)]
+    #[bake(template = DocTemplate, template_derive(Debug))]
+    pub enum Doc {
+        #[interpolate]
+        Number(i64),
+        #[interpolate(elements)]
+        List(Vec<Doc>),
+    }
+
+    #[derive(Bake)]
+    #[bake(template(PointTemplate))]
+    pub struct Point {
+        #[interpolate]
+        x: i64,
+    }
+
+    /// The twin exists with and without the 'macro' feature and converts back with `TryFrom`
+    #[test]
+    fn template_twin() {
+        let template = DocTemplate::List(vec![Interpolatable::Actual(Doc::Number(1))]);
+        assert_eq!(template.to_stream().to_string(), Doc::List(vec![Doc::Number(1)]).to_stream().to_string());
+        assert_eq!(Doc::try_from(template).unwrap(), Doc::List(vec![Doc::Number(1)]));
+
+        let hole = DocTemplate::Number(Interpolatable::Inter(parse_quote!({ n })));
+        let baked = hole.to_stream().to_string();
+        assert!(baked.ends_with(&quote!(Doc::Number({ n })).to_string()), "{baked}");
+        assert!(Doc::try_from(hole).is_err());
+
+        let point = Point::try_from(PointTemplate { x: Interpolatable::Actual(1) }).unwrap();
+        assert_eq!(point.x, 1);
+    }
+
+    #[derive(Bake, Debug, PartialEq)]
+    #[bake(template = ShapeTemplate, constructors, accessors)]
+    pub enum Shape {
+        #[interpolate]
+        r#Type(i64),
+        #[interpolate(elements)]
+        Group(Vec<Shape>),
+        Empty,
+    }
+
+    #[test]
+    fn constructors_and_accessors() {
+        let shape = ShapeTemplate::r#type(1).unwrap();
+        assert_eq!(shape.r#type_ref().unwrap(), &1);
+        let error = shape.group_ref().unwrap_err();
+        assert!(matches!(error, AccessError::Variant { expected: "Group" }), "{error}");
+
+        let group = ShapeTemplate::group(vec![Interpolatable::Actual(Shape::Empty)]).unwrap();
+        assert_eq!(group.group_ref().unwrap(), [&Shape::Empty]);
+        let error = group.r#type_ref().unwrap_err();
+        assert!(matches!(error, AccessError::Variant { expected: "Type" }), "{error}");
+
+        let hole = ShapeTemplate::r#type(Interpolatable::Inter(parse_quote!({ n }))).unwrap();
+        assert!(matches!(hole.r#type_ref(), Err(AccessError::Interpolation(_))));
+    }
+
+    #[test]
+    fn containers_of_holes() {
+        let holes = vec![Interpolatable::Actual(1), Interpolatable::Inter(parse_quote!({ a }))];
+        assert_eq!(holes.to_stream().to_string(), quote!(vec![1i32, { a }]).to_string());
+        let option = Some(Interpolatable::<i32>::Inter(parse_quote!({ a })));
+        assert_eq!(option.to_stream().to_string(), quote!(Some({ a })).to_string());
+    }
+}
@@ -0,0 +1,119 @@
+//! nom combinators for parsers that produce [Interpolatable]s
+
+use ::nom::{
+    error::{ErrorKind, FromExternalError, ParseError},
+    Err, IResult, Parser,
+};
+
+use super::{
+    syntax::{FromScanError, ScanError},
+    HoleSyntax, Interpolatable, Interpolate, RuntimeInterpolationError,
+};
+
+/// A malformed hole is a failure, with the same error kinds as [interpolation]
+impl<I, E: ParseError<I>> FromScanError<I> for Err<E> {
+    fn from_scan_error(input: I, error: ScanError) -> Self {
+        Err::Failure(E::from_error_kind(
+            input,
+            match error {
+                ScanError::NoHole => ErrorKind::Tag,
+                ScanError::Unclosed => ErrorKind::Char,
+                ScanError::Invalid => ErrorKind::Verify,
+            },
+        ))
+    }
+}
+
+/// Parses a hole like `${...}`
+///
+/// Fails with a recoverable error if the input does not start with a hole and with a failure
+/// if the hole is never closed or does not contain a single token tree.
+pub fn interpolation<'a, T, E: ParseError<&'a str>>(
+    input: &'a str,
+) -> IResult<&'a str, Interpolatable<T>, E> {
+    interpolation_with(HoleSyntax::Dollar)(input)
+}
+
+/// Parses a hole written with `syntax`, see [interpolation]
+pub fn interpolation_with<'a, T, E: ParseError<&'a str>>(
+    syntax: HoleSyntax,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Interpolatable<T>, E> {
+    move |input| match syntax.scan(input) {
+        Ok((rest, content)) => match Interpolatable::parse_hole(content) {
+            Ok(hole) => Ok((rest, hole)),
+            Err(_) => Err(Err::Failure(E::from_error_kind(input, ErrorKind::Verify))),
+        },
+        Err(ScanError::NoHole) => Err(Err::Error(E::from_error_kind(input, ErrorKind::Tag))),
+        Err(e) => Err(Err::from_scan_error(input, e)),
+    }
+}
+
+/// Accepts a hole like `${...}` or whatever `parser` accepts
+pub fn interpolatable<'a, T, E, P>(
+    mut parser: P,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Interpolatable<T>, E>
+where
+    E: ParseError<&'a str>,
+    P: Parser<&'a str, T, E>,
+{
+    move |input| match interpolation(input) {
+        Err(Err::Error(_)) => parser
+            .parse(input)
+            .map(|(rest, value)| (rest, Interpolatable::Actual(value))),
+        result => result,
+    }
+}
+
+/// Fits the result of `parser`, a hole fails with the [RuntimeInterpolationError] at the start of the hole
+///
+/// Use this in parsers that run outside of macros and can not produce holes.
+pub fn fit_nom<I: Clone, T, U, E, P>(mut parser: P) -> impl FnMut(I) -> IResult<I, T, E>
+where
+    U: Interpolate<T>,
+    E: ParseError<I> + FromExternalError<I, RuntimeInterpolationError>,
+    P: Parser<I, U, E>,
+{
+    move |input: I| {
+        let (rest, value) = parser.parse(input.clone())?;
+        Ok((rest, value.nom(input)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::nom::{character::complete::digit1, combinator::map_res, error::Error};
+
+    use super::*;
+
+    fn number(input: &str) -> IResult<&str, Interpolatable<u8>, Error<&str>> {
+        interpolatable(map_res(digit1, str::parse))(input)
+    }
+
+    fn kind(result: IResult<&str, Interpolatable<u8>, Error<&str>>) -> Err<ErrorKind> {
+        result.unwrap_err().map(|e| e.code)
+    }
+
+    #[test]
+    fn holes_and_values() {
+        let (rest, hole) = number("${a + 1} 2").unwrap();
+        assert_eq!(rest, " 2");
+        assert!(matches!(hole, Interpolatable::Inter(_)));
+        assert!(matches!(number("2 ${a}").unwrap(), (" ${a}", Interpolatable::Actual(2))));
+    }
+
+    #[test]
+    fn malformed_holes_fail() {
+        assert_eq!(kind(number("${a")), Err::Failure(ErrorKind::Char));
+        assert_eq!(kind(number("${)}")), Err::Failure(ErrorKind::Verify));
+        // Not a hole, so the other parser is tried and its error is recoverable
+        assert_eq!(kind(number("a")), Err::Error(ErrorKind::Digit));
+    }
+
+    #[test]
+    fn fit_holes() {
+        let mut fitted = fit_nom::<_, u8, _, Error<&str>, _>(number);
+        assert_eq!(fitted("1").unwrap(), ("", 1));
+        let error = fitted("${a} 1").unwrap_err().map(|e| (e.input, e.code));
+        assert_eq!(error, Err::Failure(("${a} 1", ErrorKind::Fail)));
+    }
+}
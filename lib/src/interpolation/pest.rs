@@ -0,0 +1,88 @@
+//! Helpers to turn pest pairs into [Interpolatable]s
+//!
+//! pest has no combinators, holes are matched by a rule in the grammar instead, for example
+//!
+//! ```pest
+//! hole = @{ "$" ~ block }
+//! block = { "{" ~ (block | !"}" ~ ANY)* ~ "}" }
+//! ```
+//!
+//! and the pairs of that rule are turned into holes with [hole].
+
+use ::pest::{
+    error::{Error, ErrorVariant},
+    iterators::Pair,
+    RuleType,
+};
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+
+use super::{HoleSyntax, Interpolatable, SourceMap};
+
+/// Turns a pair that matched a hole like `${...}` into an [Interpolatable::Inter]
+///
+/// Pairs that only matched the content of the hole are accepted as well.
+pub fn hole<R: RuleType, T>(pair: Pair<R>) -> Result<Interpolatable<T>, Error<R>> {
+    hole_with(pair, HoleSyntax::Dollar)
+}
+
+/// Turns a pair that matched a hole written with `syntax` into an [Interpolatable::Inter], see [hole]
+pub fn hole_with<R: RuleType, T>(pair: Pair<R>, syntax: HoleSyntax) -> Result<Interpolatable<T>, Error<R>> {
+    let text = pair.as_str();
+    let content = match syntax.scan(text) {
+        Ok(("", content)) => content,
+        _ => text,
+    };
+
+    Interpolatable::parse_hole(content).map_err(|e| {
+        Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: format!("Invalid interpolation: {e}"),
+            },
+            pair.as_span(),
+        )
+    })
+}
+
+/// Like [hole] but the tokens of the hole point at the macro input the text of `map` was rendered from
+///
+/// Only works if the pair comes from parsing [map.text()](SourceMap::text).
+pub fn spanned_hole<R: RuleType, T>(pair: Pair<R>, map: &SourceMap) -> Result<Interpolatable<T>, Error<R>> {
+    spanned_hole_with(pair, map, HoleSyntax::Dollar)
+}
+
+/// Like [spanned_hole] for holes written with `syntax`
+pub fn spanned_hole_with<R: RuleType, T>(
+    pair: Pair<R>,
+    map: &SourceMap,
+    syntax: HoleSyntax,
+) -> Result<Interpolatable<T>, Error<R>> {
+    let span = map.span_of(pair.as_span().start()..pair.as_span().end());
+    Ok(match hole_with(pair, syntax)? {
+        Interpolatable::Inter(tree) => Interpolatable::Inter(respan(tree, span)),
+        actual => actual,
+    })
+}
+
+/// Turns pairs of `hole_rule` into holes and all other pairs into values with `parser`
+pub fn interpolatable<R: RuleType, T>(
+    pair: Pair<R>,
+    hole_rule: R,
+    parser: impl FnOnce(Pair<R>) -> Result<T, Error<R>>,
+) -> Result<Interpolatable<T>, Error<R>> {
+    match pair.as_rule() == hole_rule {
+        true => hole(pair),
+        false => parser(pair).map(Interpolatable::Actual),
+    }
+}
+
+fn respan(tree: TokenTree, span: Span) -> TokenTree {
+    let mut tree = match tree {
+        TokenTree::Group(group) => {
+            let stream: TokenStream = group.stream().into_iter().map(|tree| respan(tree, span)).collect();
+            TokenTree::Group(Group::new(group.delimiter(), stream))
+        }
+        tree => tree,
+    };
+    tree.set_span(span);
+    tree
+}
@@ -0,0 +1,331 @@
+use std::{
+    any::{type_name, Any},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque},
+    error::Error,
+    fmt::{self, Display, Formatter},
+    hash::Hash,
+    sync::Arc,
+};
+
+use proc_macro2::{Delimiter, TokenTree};
+
+use crate::Bake;
+
+use super::{FlattenInterpolation, Interpolatable, Interpolate, RuntimeInterpolationError};
+
+type Value = Arc<dyn Fn() -> Box<dyn Any> + Send + Sync>;
+
+/// Values that are filled into named holes while a [Template] is rendered at runtime
+///
+/// The context is `Send + Sync`, so it can be built once and shared between threads.
+#[derive(Clone, Default)]
+pub struct RenderContext {
+    values: HashMap<String, Value>,
+}
+
+impl RenderContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value for all holes called `name`
+    ///
+    /// The value must have exactly the type that is expected at the position of the hole,
+    /// there is no `into()` conversion at runtime.
+    pub fn insert<V: Bake + Clone + Send + Sync + 'static>(&mut self, name: impl Into<String>, value: V) {
+        self.values
+            .insert(name.into(), Arc::new(move || Box::new(value.clone())));
+    }
+
+    pub fn with<V: Bake + Clone + Send + Sync + 'static>(mut self, name: impl Into<String>, value: V) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    /// The value for `name`, `Ok(None)` if there is none and an error if it is not a `T`
+    pub fn get<T: 'static>(&self, name: &str) -> Result<Option<T>, RenderError> {
+        match self.values.get(name) {
+            Some(value) => value().downcast().map(|value| Some(*value)).map_err(|_| RenderError::Type {
+                name: name.to_owned(),
+                expected: type_name::<T>(),
+            }),
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    /// The value for the hole called `name` is not of the `expected` type
+    Type { name: String, expected: &'static str },
+    /// A hole could not be filled from the context
+    Interpolation(RuntimeInterpolationError),
+}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Type { name, expected } => {
+                write!(f, "The value of `{name}` in the render context is not a `{expected}`")
+            }
+            RenderError::Interpolation(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for RenderError {}
+
+/// The name of a hole created by [named_hole](Interpolatable::named_hole) or by
+/// [parse_hole](Interpolatable::parse_hole) with a single identifier
+fn hole_name(tree: &TokenTree) -> Option<String> {
+    let TokenTree::Group(group) = tree else {
+        return None;
+    };
+    if group.delimiter() != Delimiter::Brace {
+        return None;
+    }
+    let mut tokens = group.stream().into_iter();
+    let Some(TokenTree::Ident(name)) = tokens.next() else {
+        return None;
+    };
+    let rest: Vec<_> = tokens.map(|token| token.to_string()).collect();
+    (rest.is_empty() || rest == [".", "into", "()"]).then(|| name.to_string())
+}
+
+/// Values whose named holes can be filled from a [RenderContext]
+///
+/// Implemented for [Interpolatable] and the containers that implement [FlattenInterpolation], fill the
+/// fields of your own types that contain holes. Holes that were already flattened into a larger hole
+/// are only tokens and can not be filled anymore.
+pub trait FillHoles {
+    /// Turns every named hole with a value in `context` into an `Actual`, other holes are kept
+    fn fill_holes(&mut self, context: &RenderContext) -> Result<(), RenderError>;
+}
+
+impl<T: 'static> FillHoles for Interpolatable<T> {
+    fn fill_holes(&mut self, context: &RenderContext) -> Result<(), RenderError> {
+        if let Interpolatable::Inter(tree) = self {
+            if let Some(value) = hole_name(tree).map(|name| context.get(&name)).transpose()?.flatten() {
+                *self = Interpolatable::Actual(value);
+            }
+        }
+        Ok(())
+    }
+}
+
+macro_rules! impl_lists {
+    ($($T:ident),*) => {
+        $(impl<T: FillHoles> FillHoles for $T<T> {
+            fn fill_holes(&mut self, context: &RenderContext) -> Result<(), RenderError> {
+                self.iter_mut().try_for_each(|element| element.fill_holes(context))
+            }
+        })*
+    };
+}
+
+impl_lists!(Vec, VecDeque, LinkedList, Option);
+
+impl<T: FillHoles + ?Sized> FillHoles for Box<T> {
+    fn fill_holes(&mut self, context: &RenderContext) -> Result<(), RenderError> {
+        (**self).fill_holes(context)
+    }
+}
+
+impl<T: FillHoles, const S: usize> FillHoles for [T; S] {
+    fn fill_holes(&mut self, context: &RenderContext) -> Result<(), RenderError> {
+        self.iter_mut().try_for_each(|element| element.fill_holes(context))
+    }
+}
+
+impl<T: FillHoles, E: FillHoles> FillHoles for Result<T, E> {
+    fn fill_holes(&mut self, context: &RenderContext) -> Result<(), RenderError> {
+        match self {
+            Ok(t) => t.fill_holes(context),
+            Err(e) => e.fill_holes(context),
+        }
+    }
+}
+
+/// Elements and keys can not be changed in place, so the collection is rebuilt
+macro_rules! impl_sets {
+    ($($T:ident: $($bound:path)*),*) => {
+        $(impl<T: FillHoles $(+ $bound)*> FillHoles for $T<T> {
+            fn fill_holes(&mut self, context: &RenderContext) -> Result<(), RenderError> {
+                *self = std::mem::take(self)
+                    .into_iter()
+                    .map(|mut element| element.fill_holes(context).map(|_| element))
+                    .collect::<Result<_, _>>()?;
+                Ok(())
+            }
+        })*
+    };
+}
+
+impl_sets!(BTreeSet: Ord, HashSet: Eq Hash);
+
+macro_rules! impl_maps {
+    ($($T:ident: $($bound:path)*),*) => {
+        $(impl<K: FillHoles $(+ $bound)*, V: FillHoles> FillHoles for $T<K, V> {
+            fn fill_holes(&mut self, context: &RenderContext) -> Result<(), RenderError> {
+                *self = std::mem::take(self)
+                    .into_iter()
+                    .map(|mut entry| entry.fill_holes(context).map(|_| entry))
+                    .collect::<Result<_, _>>()?;
+                Ok(())
+            }
+        })*
+    };
+}
+
+impl_maps!(BTreeMap: Ord, HashMap: Eq Hash);
+
+macro_rules! impl_tuple {
+    ($($($T:ident)+),*) => {
+        $(
+            impl<$($T: FillHoles),*> FillHoles for ($($T,)*) {
+                #[allow(non_snake_case)]
+                fn fill_holes(&mut self, context: &RenderContext) -> Result<(), RenderError> {
+                    let ($($T,)*) = self;
+                    $($T.fill_holes(context)?;)*
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple!(
+    A,
+    A B,
+    A B C,
+    A B C D,
+    A B C D E,
+    A B C D E F,
+    A B C D E F G,
+    A B C D E F G H,
+    A B C D E F G H I,
+    A B C D E F G H I J,
+    A B C D E F G H I J K,
+    A B C D E F G H I J K L,
+    A B C D E F G H I J K L M,
+    A B C D E F G H I J K L M N,
+    A B C D E F G H I J K L M N O,
+    A B C D E F G H I J K L M N O P
+);
+
+/// A source that is parsed once at runtime and whose named holes are filled from a [RenderContext]
+///
+/// The parser creates holes with [named_hole](Interpolatable::named_hole) and returns them in a value
+/// that implements [FillHoles], like `Vec<Interpolatable<T>>`. Every render fills a copy of that value
+/// and flattens it, so the holes must not be flattened by the parser. Holes are token trees, so unlike
+/// the context a template can not be shared between threads.
+pub struct Template<P> {
+    source: String,
+    parsed: P,
+}
+
+impl<P> Template<P> {
+    pub fn new<E>(source: impl Into<String>, parser: impl FnOnce(&str) -> Result<P, E>) -> Result<Self, E> {
+        let source = source.into();
+        let parsed = parser(&source)?;
+        Ok(Self { source, parsed })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn parsed(&self) -> &P {
+        &self.parsed
+    }
+
+    /// The template with all named holes filled from `context`
+    ///
+    /// Fails if a value has another type than its hole or if a hole has no value.
+    pub fn render<T: Bake>(&self, context: &RenderContext) -> Result<T, RenderError>
+    where
+        P: FillHoles + FlattenInterpolation<T> + Clone,
+    {
+        let mut parsed = self.parsed.clone();
+        parsed.fill_holes(context)?;
+        parsed.flatten_interpolation().fit().map_err(RenderError::Interpolation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::Ident;
+    use quote::format_ident;
+
+    use super::*;
+
+    fn hole(name: &str) -> Interpolatable<String> {
+        Interpolatable::named_hole(format_ident!("{name}"))
+    }
+
+    /// Splits the source at `,` and turns `$name` into a hole
+    fn parse(source: &str) -> syn::Result<Vec<Interpolatable<String>>> {
+        source
+            .split(',')
+            .map(|part| match part.strip_prefix('$') {
+                Some(name) => syn::parse_str::<Ident>(name).map(Interpolatable::named_hole),
+                None => Ok(Interpolatable::Actual(part.to_owned())),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn context_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<RenderContext>();
+        assert_send_sync::<RenderError>();
+    }
+
+    #[test]
+    fn filled_hole() {
+        let template = Template::new("a,$name,$name", parse).unwrap();
+        let context = RenderContext::new().with("name", "b".to_owned());
+        let rendered: Vec<String> = template.render(&context).unwrap();
+        assert_eq!(rendered, ["a", "b", "b"]);
+        let context = RenderContext::new().with("name", "c".to_owned());
+        assert_eq!(template.render::<Vec<String>>(&context).unwrap(), ["a", "c", "c"]);
+    }
+
+    #[test]
+    fn missing_key() {
+        let template = Template::new("a,$name", parse).unwrap();
+        let context = RenderContext::new().with("other", "b".to_owned());
+        let error = template.render::<Vec<String>>(&context).unwrap_err();
+        let RenderError::Interpolation(error) = error else {
+            panic!("{error}");
+        };
+        assert!(error.hole().text().contains("name"), "{}", error.hole());
+    }
+
+    #[test]
+    fn wrong_type() {
+        let mut value = hole("name");
+        let error = value.fill_holes(&RenderContext::new().with("name", 1u8)).unwrap_err();
+        assert!(matches!(&error, RenderError::Type { name, .. } if name == "name"), "{error}");
+        assert!(matches!(value, Interpolatable::Inter(_)));
+    }
+
+    #[test]
+    fn parsed_holes() {
+        let mut value = Interpolatable::<String>::parse_hole("name").unwrap();
+        value.fill_holes(&RenderContext::new().with("name", "a".to_owned())).unwrap();
+        assert!(matches!(value, Interpolatable::Actual(ref a) if a == "a"));
+
+        let mut value = Interpolatable::<String>::parse_hole("name + 1").unwrap();
+        value.fill_holes(&RenderContext::new().with("name", "a".to_owned())).unwrap();
+        assert!(matches!(value, Interpolatable::Inter(_)));
+    }
+}
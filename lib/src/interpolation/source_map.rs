@@ -0,0 +1,186 @@
+use std::{fmt::Display, ops::Range};
+
+use proc_macro2::{Delimiter, Literal, Spacing, Span, TokenStream, TokenTree};
+use syn::LitStr;
+
+/// The text of a macro input together with the span every part of the text came from
+///
+/// Lets string based parsers report errors at the right token: a parse error at byte offset `N` of [text](Self::text)
+/// becomes a `syn::Error` that points at the token that produced the text at `N`.
+pub struct SourceMap {
+    text: String,
+    spans: Vec<(Range<usize>, Span)>,
+    fallback: Span,
+}
+
+impl SourceMap {
+    /// Renders the tokens to text, separated by a single space unless a punctuation is joint with the next token
+    ///
+    /// A punctuation is also never separated from a following group, so holes like `${..}` keep their shape.
+    pub fn from_tokens(stream: TokenStream) -> Self {
+        let mut map = Self {
+            text: String::new(),
+            spans: Vec::new(),
+            fallback: Span::call_site(),
+        };
+        map.render(stream);
+        if let Some((_, span)) = map.spans.last() {
+            map.fallback = *span;
+        }
+        map
+    }
+
+    /// Uses the value of a string literal as the text, fails for other literals
+    ///
+    /// Offsets are mapped to parts of the literal where the compiler supports it and the literal
+    /// contains no escapes, otherwise to the whole literal.
+    pub fn from_literal(literal: &Literal) -> syn::Result<Self> {
+        let text = syn::parse2::<LitStr>(TokenTree::Literal(literal.clone()).into())?.value();
+
+        let source = literal.to_string();
+        let prefix = source.find('"').map_or(0, |quote| quote + 1);
+        let verbatim = source.get(prefix..prefix + text.len()) == Some(text.as_str());
+
+        let mut spans = Vec::new();
+        if verbatim {
+            for (offset, c) in text.char_indices() {
+                let start = prefix + offset;
+                if let Some(span) = literal.subspan(start..start + c.len_utf8()) {
+                    spans.push((offset..offset + c.len_utf8(), span));
+                }
+            }
+        }
+
+        Ok(Self {
+            text,
+            spans,
+            fallback: literal.span(),
+        })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The span of the token that produced the text at `offset`
+    ///
+    /// Whitespace between tokens belongs to the following token, offsets behind the end to the last token.
+    pub fn span_at(&self, offset: usize) -> Span {
+        self.spans
+            .iter()
+            .find(|(range, _)| offset < range.end)
+            .map_or(self.fallback, |(_, span)| *span)
+    }
+
+    /// The span that covers `range`, only the start if the compiler can not join spans
+    pub fn span_of(&self, range: Range<usize>) -> Span {
+        let start = self.span_at(range.start);
+        let end = self.span_at(range.end.saturating_sub(1).max(range.start));
+        start.join(end).unwrap_or(start)
+    }
+
+    /// The offset of `rest` in the text, `rest` has to be a suffix of the text like the remaining input of nom
+    pub fn offset_of(&self, rest: &str) -> usize {
+        self.text.len().saturating_sub(rest.len())
+    }
+
+    pub fn error_at(&self, offset: usize, message: impl Display) -> syn::Error {
+        syn::Error::new(self.span_at(offset), message)
+    }
+
+    /// An error at the start of the remaining input `rest`
+    pub fn error_at_rest(&self, rest: &str, message: impl Display) -> syn::Error {
+        self.error_at(self.offset_of(rest), message)
+    }
+
+    fn push(&mut self, text: &str, span: Span) {
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.spans.push((start..self.text.len(), span));
+    }
+
+    fn render(&mut self, stream: TokenStream) {
+        let mut joint = true;
+        let mut punct = false;
+        for tree in stream {
+            if !joint && (!punct || !matches!(tree, TokenTree::Group(_))) {
+                self.text.push(' ');
+            }
+            joint = false;
+            punct = matches!(tree, TokenTree::Punct(_));
+
+            match tree {
+                TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    self.push(open, group.span_open());
+                    self.render(group.stream());
+                    self.push(close, group.span_close());
+                }
+                TokenTree::Punct(p) => {
+                    self.push(&p.as_char().to_string(), p.span());
+                    joint = p.spacing() == Spacing::Joint;
+                }
+                TokenTree::Ident(ident) => self.push(&ident.to_string(), ident.span()),
+                TokenTree::Literal(literal) => self.push(&literal.to_string(), literal.span()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(source: &str) -> SourceMap {
+        SourceMap::from_tokens(source.parse().unwrap())
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(map("a + ${ b } (c,d) -> e").text(), "a + ${b} (c , d) -> e");
+        assert_eq!(map("a+b").text(), "a + b");
+        assert_eq!(map("a+=b").text(), "a += b");
+        assert_eq!(map("").text(), "");
+    }
+
+    #[test]
+    fn offsets() {
+        let map = map("a + ${b} (c)");
+        let rest = &map.text()[map.text().find('(').unwrap()..];
+        assert_eq!(map.offset_of(rest), 9);
+        assert_eq!(map.offset_of(""), map.text().len());
+        assert!(map.error_at_rest(rest, "here").to_string().contains("here"));
+    }
+
+    #[cfg(feature = "locations")]
+    #[test]
+    fn spans() {
+        let map = map("first + ${ second }\n  (third)");
+        let column = |offset: usize| {
+            let start = map.span_at(offset).start();
+            (start.line, start.column)
+        };
+        assert_eq!(column(0), (1, 0));
+        assert_eq!(column(4), (1, 0));
+        assert_eq!(column(6), (1, 6));
+        // The whitespace before a token belongs to that token
+        assert_eq!(column(7), (1, 8));
+        assert_eq!(column(map.text().find("second").unwrap() + 3), (1, 11));
+        assert_eq!(column(map.text().find("third").unwrap()), (2, 3));
+        // Offsets behind the end point at the last token
+        assert_eq!(column(100), (2, 8));
+    }
+
+    #[test]
+    fn literals() {
+        let literal: Literal = syn::parse_str("\"a ${b}\"").unwrap();
+        let map = SourceMap::from_literal(&literal).unwrap();
+        assert_eq!(map.text(), "a ${b}");
+        assert!(SourceMap::from_literal(&Literal::u8_suffixed(1)).is_err());
+    }
+}
@@ -0,0 +1,275 @@
+use proc_macro2::{Delimiter, Group, Literal, Span, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+
+use crate::{
+    path::{FieldPath, PathSegment},
+    Bake, BakeError, Baker,
+};
+
+use super::{
+    syntax::{walk, HoleSyntax, ScanError},
+    Interpolatable, VisitInterpolations, VisitInterpolationsMut,
+};
+
+/// A piece of an [InterpolatedString]
+#[derive(Debug, Clone)]
+pub enum StringSegment {
+    /// Text that is copied into the string as-is
+    Literal(String),
+    /// A rust expression that gets formatted into the string at runtime
+    ///
+    /// `spec` is everything that would follow the `:` in a `format!` placeholder, e.g. `>5` or `?`
+    Hole {
+        expr: TokenTree,
+        spec: Option<String>,
+    },
+}
+
+/// A string literal with embedded holes like `"Hello ${name}, you have ${n:>3} items"`
+///
+/// Bakes to a `&'static str` if there are no holes and to a `format!(...)` otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct InterpolatedString {
+    segments: Vec<StringSegment>,
+}
+
+impl InterpolatedString {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends literal text, merging it with a directly preceding literal
+    pub fn push_literal(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        match self.segments.last_mut() {
+            Some(StringSegment::Literal(last)) => last.push_str(text),
+            _ => self.segments.push(StringSegment::Literal(text.to_owned())),
+        }
+    }
+
+    pub fn push_hole(&mut self, expr: TokenTree, spec: Option<String>) {
+        self.segments.push(StringSegment::Hole { expr, spec })
+    }
+
+    pub fn segments(&self) -> &[StringSegment] {
+        &self.segments
+    }
+
+    pub fn has_holes(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, StringSegment::Hole { .. }))
+    }
+
+    /// Parses the content of a string (without the surrounding quotes) into segments
+    ///
+    /// Holes are written as `${expr}` or `${expr:spec}`, a literal `$` can be escaped as `$$`.
+    pub fn parse(input: &str) -> syn::Result<Self> {
+        let mut result = Self::new();
+        let mut rest = input;
+
+        while let Some(index) = rest.find('$') {
+            result.push_literal(&rest[..index]);
+            let tail = &rest[index..];
+
+            if let Some(tail) = tail.strip_prefix("$$") {
+                result.push_literal("$");
+                rest = tail;
+                continue;
+            }
+
+            let (tail, content) = match HoleSyntax::Dollar.scan(tail) {
+                Ok(scanned) => scanned,
+                Err(ScanError::NoHole) => {
+                    result.push_literal("$");
+                    rest = &tail[1..];
+                    continue;
+                }
+                Err(e) => {
+                    return Err(syn::Error::new(
+                        Span::call_site(),
+                        format!("{e} in \"{input}\""),
+                    ))
+                }
+            };
+
+            let (expr, spec) = split_spec(content);
+            let expr = syn::parse_str::<syn::Expr>(expr)?.into_token_stream();
+            result.push_hole(
+                TokenTree::Group(Group::new(Delimiter::Parenthesis, expr)),
+                spec.map(str::to_owned),
+            );
+
+            rest = tail;
+        }
+
+        result.push_literal(rest);
+        Ok(result)
+    }
+
+    /// The format string that `format!` is called with, literal braces are escaped
+    pub fn format_string(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                StringSegment::Literal(text) => text.replace('{', "{{").replace('}', "}}"),
+                StringSegment::Hole { spec: None, .. } => "{}".to_owned(),
+                StringSegment::Hole {
+                    spec: Some(spec), ..
+                } => format!("{{:{spec}}}"),
+            })
+            .collect()
+    }
+
+    /// All literal text with the holes left out
+    fn literal_text(&self) -> String {
+        self.segments
+            .iter()
+            .filter_map(|segment| match segment {
+                StringSegment::Literal(text) => Some(text.as_str()),
+                StringSegment::Hole { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Converts into an `Actual` if there are no holes and into an interpolation of `format!(...)` otherwise
+    pub fn into_interpolatable(self) -> Interpolatable<String> {
+        if self.has_holes() {
+            let stream = self.to_stream();
+            Interpolatable::Inter(TokenTree::Group(Group::new(Delimiter::Brace, stream)))
+        } else {
+            Interpolatable::Actual(self.literal_text())
+        }
+    }
+}
+
+impl FromIterator<StringSegment> for InterpolatedString {
+    fn from_iter<T: IntoIterator<Item = StringSegment>>(iter: T) -> Self {
+        let mut result = Self::new();
+        for segment in iter {
+            match segment {
+                StringSegment::Literal(text) => result.push_literal(&text),
+                hole => result.segments.push(hole),
+            }
+        }
+        result
+    }
+}
+
+impl From<InterpolatedString> for Interpolatable<String> {
+    fn from(value: InterpolatedString) -> Self {
+        value.into_interpolatable()
+    }
+}
+
+impl Bake for InterpolatedString {
+    fn to_stream(&self) -> TokenStream {
+        if !self.has_holes() {
+            let text = Literal::string(&self.literal_text());
+            return quote!(#text);
+        }
+
+        let format_string = Literal::string(&self.format_string());
+        let args = self.segments.iter().filter_map(|segment| match segment {
+            StringSegment::Hole { expr, .. } => Some(expr),
+            StringSegment::Literal(_) => None,
+        });
+
+        quote!(format!(#format_string, #(#args),*))
+    }
+
+    /// A string without holes is a literal, otherwise the `format!` call is passed on as tokens
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        Ok(match self.has_holes() {
+            false => baker.literal(self.to_stream()),
+            true => baker.tokens(self.to_stream()),
+        })
+    }
+}
+
+/// Every hole is visited with its position among the holes of the string
+impl VisitInterpolations for InterpolatedString {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+        let holes = self.segments.iter().filter_map(|segment| match segment {
+            StringSegment::Hole { expr, .. } => Some(expr),
+            StringSegment::Literal(_) => None,
+        });
+        for (index, expr) in holes.enumerate() {
+            path.with(PathSegment::Index(index), |path| visitor(path, expr));
+        }
+    }
+}
+
+impl VisitInterpolationsMut for InterpolatedString {
+    fn visit_interpolations_mut(
+        &mut self,
+        path: &mut FieldPath,
+        visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree),
+    ) {
+        let holes = self.segments.iter_mut().filter_map(|segment| match segment {
+            StringSegment::Hole { expr, .. } => Some(expr),
+            StringSegment::Literal(_) => None,
+        });
+        for (index, expr) in holes.enumerate() {
+            path.with(PathSegment::Index(index), |path| visitor(path, expr));
+        }
+    }
+}
+
+/// Splits `expr:spec` at the last `:` that is not nested and not part of a `::`
+fn split_spec(hole: &str) -> (&str, Option<&str>) {
+    let bytes = hole.as_bytes();
+    let mut split = None;
+
+    walk(hole, |index, c, depth| {
+        if c == ':' && depth == 0 {
+            let double = bytes.get(index + 1) == Some(&b':') || (index > 0 && bytes[index - 1] == b':');
+            if !double {
+                split = Some(index);
+            }
+        }
+        false
+    });
+
+    match split {
+        Some(index) => (&hole[..index], Some(hole[index + 1..].trim())),
+        None => (hole, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        let string = InterpolatedString::parse("costs $$5, $ alone {x}").unwrap();
+        assert!(!string.has_holes());
+        assert_eq!(string.to_stream().to_string(), quote!("costs $5, $ alone {x}").to_string());
+        assert!(matches!(string.into_interpolatable(), Interpolatable::Actual(text) if text == "costs $5, $ alone {x}"));
+    }
+
+    #[test]
+    fn holes_and_specs() {
+        let string = InterpolatedString::parse("{${name}} ${n:>3} ${std::f64::consts::PI:.2}").unwrap();
+        assert_eq!(string.format_string(), "{{{}}} {:>3} {:.2}");
+        assert_eq!(
+            string.to_stream().to_string(),
+            quote!(format!("{{{}}} {:>3} {:.2}", (name), (n), (std::f64::consts::PI))).to_string()
+        );
+        assert!(matches!(string.into_interpolatable(), Interpolatable::Inter(_)));
+        assert!(InterpolatedString::parse("${a").is_err());
+    }
+
+    #[test]
+    fn split_specs() {
+        assert_eq!(split_spec("a"), ("a", None));
+        assert_eq!(split_spec("a:?"), ("a", Some("?")));
+        assert_eq!(split_spec("a::b"), ("a::b", None));
+        assert_eq!(split_spec("a::b: >5"), ("a::b", Some(">5")));
+        assert_eq!(split_spec("f(x: u8)"), ("f(x: u8)", None));
+        assert_eq!(split_spec("\":\""), ("\":\"", None));
+    }
+}
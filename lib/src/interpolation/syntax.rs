@@ -0,0 +1,325 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use super::Interpolatable;
+
+/// The delimiters that mark a hole in the input of a parser
+///
+/// The content of a hole is rust code, braces inside of string and char literals or comments
+/// are ignored when looking for the end of the hole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HoleSyntax {
+    /// `${...}`
+    #[default]
+    Dollar,
+    /// `#{...}`
+    Hash,
+    /// `{{...}}`
+    DoubleBrace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanError {
+    /// The input does not start with a hole
+    NoHole,
+    /// The hole is never closed
+    Unclosed,
+    /// The content of the hole is not a single token tree, only returned by [HoleSyntax::interpolatable]
+    Invalid,
+}
+
+impl Display for ScanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::NoHole => write!(f, "Expected an interpolation"),
+            ScanError::Unclosed => write!(f, "Unclosed interpolation"),
+            ScanError::Invalid => write!(f, "Invalid interpolation"),
+        }
+    }
+}
+
+impl Error for ScanError {}
+
+/// Errors that [HoleSyntax::interpolatable] can report for a malformed hole at `input`
+///
+/// Implemented for nom errors with the `nom` feature, a malformed hole is a failure so `alt` does not try other branches.
+pub trait FromScanError<I> {
+    fn from_scan_error(input: I, error: ScanError) -> Self;
+}
+
+impl<I> FromScanError<I> for ScanError {
+    fn from_scan_error(_: I, error: ScanError) -> Self {
+        error
+    }
+}
+
+impl HoleSyntax {
+    pub fn opening(self) -> &'static str {
+        match self {
+            HoleSyntax::Dollar => "${",
+            HoleSyntax::Hash => "#{",
+            HoleSyntax::DoubleBrace => "{{",
+        }
+    }
+
+    pub fn closing(self) -> &'static str {
+        match self {
+            HoleSyntax::Dollar | HoleSyntax::Hash => "}",
+            HoleSyntax::DoubleBrace => "}}",
+        }
+    }
+
+    pub fn starts_hole(self, input: &str) -> bool {
+        input.starts_with(self.opening())
+    }
+
+    /// Splits an input that starts with a hole into the remaining input and the content of the hole
+    pub fn scan(self, input: &str) -> Result<(&str, &str), ScanError> {
+        let content = input
+            .strip_prefix(self.opening())
+            .ok_or(ScanError::NoHole)?;
+
+        let closing = self.closing();
+        let end = walk(content, |index, c, depth| {
+            depth == 0 && c == '}' && content[index..].starts_with(closing)
+        })
+        .ok_or(ScanError::Unclosed)?;
+
+        Ok((&content[end + closing.len()..], &content[..end]))
+    }
+
+    /// Finds the first hole and returns the text in front of it, its content and the remaining input
+    pub fn find(self, input: &str) -> Result<Option<(&str, &str, &str)>, ScanError> {
+        match input.find(self.opening()) {
+            Some(start) => {
+                let (rest, content) = self.scan(&input[start..])?;
+                Ok(Some((&input[..start], content, rest)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Wraps a parser so it also accepts a hole in its position
+    ///
+    /// Works with every parser of the shape `FnMut(&str) -> Result<(&str, T), E>`, which includes nom parsers.
+    /// Once the opening delimiter matched a malformed hole is an error and the inner parser is not called.
+    pub fn interpolatable<'a, T, E, P>(
+        self,
+        mut parser: P,
+    ) -> impl FnMut(&'a str) -> Result<(&'a str, Interpolatable<T>), E>
+    where
+        E: FromScanError<&'a str>,
+        P: FnMut(&'a str) -> Result<(&'a str, T), E>,
+    {
+        move |input| match self.scan(input) {
+            Ok((rest, content)) => match Interpolatable::parse_hole(content) {
+                Ok(hole) => Ok((rest, hole)),
+                Err(_) => Err(E::from_scan_error(input, ScanError::Invalid)),
+            },
+            Err(ScanError::NoHole) => {
+                let (rest, value) = parser(input)?;
+                Ok((rest, Interpolatable::Actual(value)))
+            }
+            Err(e) => Err(E::from_scan_error(input, e)),
+        }
+    }
+}
+
+/// Wraps a parser so it also accepts a hole like `${...}` in its position, see [HoleSyntax::interpolatable]
+pub fn interpolatable<'a, T, E, P>(
+    parser: P,
+) -> impl FnMut(&'a str) -> Result<(&'a str, Interpolatable<T>), E>
+where
+    E: FromScanError<&'a str>,
+    P: FnMut(&'a str) -> Result<(&'a str, T), E>,
+{
+    HoleSyntax::Dollar.interpolatable(parser)
+}
+
+/// Calls `f` with the byte index, the char and the nesting depth of every char of rust code
+/// that is not part of a literal or comment, stops at the first index for which `f` returns `true`
+pub(crate) fn walk(input: &str, mut f: impl FnMut(usize, char, usize) -> bool) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut index = 0;
+    let mut previous = None;
+
+    while let Some(c) = input[index..].chars().next() {
+        let rest = &input[index..];
+        let is_prefix = !previous.is_some_and(|p: char| p.is_alphanumeric() || p == '_');
+
+        if let Some(length) = comment_length(rest).or_else(|| literal_length(rest, is_prefix)) {
+            index += length;
+            previous = None;
+            continue;
+        }
+
+        if f(index, c, depth) {
+            return Some(index);
+        }
+
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+
+        index += c.len_utf8();
+        previous = Some(c);
+    }
+
+    None
+}
+
+/// The length of a comment at the start of `input`, block comments may be nested
+fn comment_length(input: &str) -> Option<usize> {
+    if input.starts_with("//") {
+        return Some(input.find('\n').unwrap_or(input.len()));
+    }
+
+    if !input.starts_with("/*") {
+        return None;
+    }
+
+    let mut depth = 0usize;
+    let mut index = 0;
+    while index < input.len() {
+        let rest = &input[index..];
+        if rest.starts_with("/*") {
+            depth += 1;
+            index += 2;
+        } else if rest.starts_with("*/") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                return Some(index);
+            }
+        } else {
+            index += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    Some(input.len())
+}
+
+/// The length of a string, byte string, raw string or char literal at the start of `input`
+///
+/// Lifetimes are not literals. Prefixes like `r` or `b` are only recognized at the start of a token.
+fn literal_length(input: &str, is_prefix: bool) -> Option<usize> {
+    let (prefix, rest) = match input.strip_prefix('b') {
+        Some(rest) if is_prefix => (1, rest),
+        _ => (0, input),
+    };
+
+    if let Some(raw) = rest.strip_prefix('r').filter(|_| is_prefix) {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        if raw[hashes..].starts_with('"') {
+            let terminator = format!("\"{}", "#".repeat(hashes));
+            let body = &raw[hashes + 1..];
+            let end = body
+                .find(&terminator)
+                .map_or(body.len(), |end| end + terminator.len());
+            return Some(prefix + 1 + hashes + 1 + end);
+        }
+        return None;
+    }
+
+    if let Some(body) = rest.strip_prefix('"') {
+        return Some(prefix + 1 + quoted_length(body, '"'));
+    }
+
+    if let Some(body) = rest.strip_prefix('\'') {
+        let mut chars = body.chars();
+        return match (chars.next(), chars.next()) {
+            (Some('\\'), _) => Some(prefix + 1 + quoted_length(body, '\'')),
+            (Some(c), Some('\'')) => Some(prefix + 1 + c.len_utf8() + 1),
+            _ => None,
+        };
+    }
+
+    None
+}
+
+/// The length of the body of a quoted literal including the closing quote
+fn quoted_length(body: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (index, c) in body.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return index + 1,
+            _ => (),
+        }
+    }
+    body.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_skips_literals_and_comments() {
+        let dollar = HoleSyntax::Dollar;
+        assert_eq!(dollar.scan(r##"${r#"}"#} rest"##), Ok((" rest", r##"r#"}"#"##)));
+        assert_eq!(dollar.scan("${'}'}x"), Ok(("x", "'}'")));
+        assert_eq!(dollar.scan("${x: &'a str}x"), Ok(("x", "x: &'a str")));
+        assert_eq!(dollar.scan("${a /* } /* } */ } */ b}x"), Ok(("x", "a /* } /* } */ } */ b")));
+        assert_eq!(dollar.scan(r#"${"a:b"}"#), Ok(("", r#""a:b""#)));
+        assert_eq!(dollar.scan("${{a}}}"), Ok(("}", "{a}")));
+        assert_eq!(dollar.scan("${a"), Err(ScanError::Unclosed));
+        assert_eq!(dollar.scan("a"), Err(ScanError::NoHole));
+        assert_eq!(HoleSyntax::DoubleBrace.scan("{{a}}b"), Ok(("b", "a")));
+    }
+
+    #[test]
+    fn walk_tracks_depth() {
+        let closing = |input: &str| walk(input, |_, c, depth| depth == 0 && c == '}');
+        assert_eq!(closing("(})}"), Some(3));
+        assert_eq!(closing(r#""}"}"#), Some(3));
+        assert_eq!(closing("'}'}"), Some(3));
+        assert_eq!(closing("'a}"), Some(2));
+        assert_eq!(closing("// }\n}"), Some(5));
+        assert_eq!(closing("br\"}\"}"), Some(5));
+        assert_eq!(closing("for\"}\"}"), Some(6));
+        assert_eq!(closing("{"), None);
+    }
+
+    #[test]
+    fn comment_lengths() {
+        assert_eq!(comment_length("// a }\nb"), Some(6));
+        assert_eq!(comment_length("// a }"), Some(6));
+        assert_eq!(comment_length("/* /* */ */x"), Some(11));
+        assert_eq!(comment_length("/* } x"), Some(6));
+        assert_eq!(comment_length("/ }"), None);
+    }
+
+    #[test]
+    fn literal_lengths() {
+        assert_eq!(literal_length(r#""}"x"#, true), Some(3));
+        assert_eq!(literal_length(r#""\"}"x"#, true), Some(5));
+        assert_eq!(literal_length(r#"b"}"x"#, true), Some(4));
+        assert_eq!(literal_length(r##"r#"}"#x"##, true), Some(6));
+        assert_eq!(literal_length(r##"br#"}"#x"##, true), Some(7));
+        assert_eq!(literal_length(r#"r"}"x"#, false), None);
+        assert_eq!(literal_length("'}'x", true), Some(3));
+        assert_eq!(literal_length(r"'\''x", true), Some(4));
+        assert_eq!(literal_length("'a}", true), None);
+        assert_eq!(literal_length("'static ", true), None);
+        assert_eq!(literal_length("r#ident", true), None);
+    }
+
+    #[test]
+    fn interpolatable_reports_malformed_holes() {
+        fn number(input: &str) -> Result<(&str, i64), ScanError> {
+            let end = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+            input[..end].parse().map(|n| (&input[end..], n)).map_err(|_| ScanError::NoHole)
+        }
+
+        let mut parser = interpolatable(number);
+        assert!(matches!(parser("12x"), Ok(("x", Interpolatable::Actual(12)))));
+        assert!(matches!(parser("${a}x"), Ok(("x", Interpolatable::Inter(_)))));
+        assert!(matches!(parser("${a"), Err(ScanError::Unclosed)));
+        assert!(matches!(parser("${)}"), Err(ScanError::Invalid)));
+    }
+}
@@ -0,0 +1,154 @@
+use proc_macro2::{Delimiter, Ident, TokenStream, TokenTree};
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse2,
+    punctuated::Punctuated,
+    Token, Type,
+};
+
+use crate::Bakeable;
+
+use super::Interpolatable;
+
+/// A single parameter of a template like `name: &str`
+#[derive(Clone)]
+pub struct TemplateParameter {
+    pub name: Ident,
+    pub ty: Type,
+}
+
+impl Parse for TemplateParameter {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        Ok(Self { name, ty })
+    }
+}
+
+/// The signature of a template like `|name: &str, count: i64| -> Json`
+///
+/// The return type is optional and will be inferred if it is left out.
+#[derive(Clone)]
+pub struct TemplateSignature {
+    pub parameters: Vec<TemplateParameter>,
+    pub output: Option<Type>,
+}
+
+impl Parse for TemplateSignature {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let parameters = if input.peek(Token![||]) {
+            input.parse::<Token![||]>()?;
+            Vec::new()
+        } else {
+            input.parse::<Token![|]>()?;
+            let mut parameters = Punctuated::<TemplateParameter, Token![,]>::new();
+            while !input.peek(Token![|]) {
+                parameters.push_value(input.parse()?);
+                if input.peek(Token![|]) {
+                    break;
+                }
+                parameters.push_punct(input.parse()?);
+            }
+            input.parse::<Token![|]>()?;
+            parameters.into_iter().collect()
+        };
+
+        let output = if input.peek(Token![->]) {
+            input.parse::<Token![->]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(Self { parameters, output })
+    }
+}
+
+impl TemplateSignature {
+    pub fn get(&self, name: &str) -> Option<&TemplateParameter> {
+        self.parameters.iter().find(|parameter| parameter.name == name)
+    }
+
+    /// Creates a hole for the parameter called `name` or `None` if there is no such parameter
+    ///
+    /// Only the identifier in the hole keeps the span of the parameter declaration.
+    pub fn hole<T>(&self, name: &str) -> Option<Interpolatable<T>> {
+        self.get(name)
+            .map(|parameter| Interpolatable::named_hole(parameter.name.clone()))
+    }
+}
+
+/// Splits the input of a template macro like `|name: &str| { ... }` into its signature and body
+///
+/// If the body is a single block its braces are removed.
+pub fn parse_template(input: TokenStream) -> syn::Result<(TemplateSignature, TokenStream)> {
+    struct Template(TemplateSignature, TokenStream);
+
+    impl Parse for Template {
+        fn parse(input: ParseStream) -> syn::Result<Self> {
+            Ok(Self(input.parse()?, input.parse()?))
+        }
+    }
+
+    let Template(signature, body) = parse2(input)?;
+
+    let mut iter = body.clone().into_iter();
+    let body = match (iter.next(), iter.next()) {
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::Brace => {
+            group.stream()
+        }
+        _ => body,
+    };
+
+    Ok((signature, body))
+}
+
+/// Bakes `value` into a closure that takes the parameters of `signature`
+///
+/// Holes created with [TemplateSignature::hole] refer to the parameters of the closure, all other parts
+/// of `value` are baked as usual and thus need no parsing when the closure is called.
+pub fn bake_template<B: Bakeable>(signature: &TemplateSignature, value: &B) -> TokenStream {
+    let names = signature.parameters.iter().map(|parameter| &parameter.name);
+    let types = signature.parameters.iter().map(|parameter| &parameter.ty);
+    let output = signature.output.as_ref().map(|output| quote!(-> #output));
+    let value = value.bake();
+
+    quote!(move |#(#names: #types),*| #output { #value })
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+
+    use super::*;
+    use crate::interpolation::FlattenInterpolation;
+
+    #[test]
+    fn signatures() {
+        let (signature, body) = parse_template(quote!(|name: &str, count: i64| -> Json { a b })).unwrap();
+        let names: Vec<_> = signature.parameters.iter().map(|p| p.name.to_string()).collect();
+        assert_eq!(names, ["name", "count"]);
+        assert_eq!(signature.output.unwrap().to_token_stream().to_string(), "Json");
+        assert_eq!(body.to_string(), "a b");
+
+        let (signature, body) = parse_template(quote!(|| { a } b)).unwrap();
+        assert!(signature.parameters.is_empty() && signature.output.is_none());
+        assert_eq!(body.to_string(), "{ a } b");
+    }
+
+    #[test]
+    fn baked_closure() {
+        let (signature, _) = parse_template(quote!(|name: &str, count: i64| {})).unwrap();
+        assert!(signature.hole::<String>("missing").is_none());
+
+        let value: Interpolatable<(String, i64)> = (
+            signature.hole::<String>("name").unwrap(),
+            Interpolatable::Actual(1i64),
+        )
+            .flatten_interpolation();
+        let expected = quote!(move |name: &str, count: i64| { { ({ name.into() }.into(), 1i64.into(),) } });
+        assert_eq!(bake_template(&signature, &value).to_string(), expected.to_string());
+    }
+}
@@ -0,0 +1,129 @@
+// Holes are compared, hashed and ordered by the text of their tokens,
+// so two holes are equal if they are written the same (ignoring whitespace).
+// Holes are ordered before all actual values.
+
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+};
+
+use proc_macro2::{Delimiter, TokenTree};
+
+use super::Interpolatable;
+
+fn hole_text(tree: &TokenTree) -> String {
+    tree.to_string()
+}
+
+impl<T: PartialEq> PartialEq for Interpolatable<T> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Actual(a), Self::Actual(b)) => a.eq(b),
+            (Self::Inter(a), Self::Inter(b)) => hole_text(a) == hole_text(b),
+            _ => false,
+        }
+    }
+}
+
+impl<T: Eq> Eq for Interpolatable<T> {}
+
+impl<T: Hash> Hash for Interpolatable<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Inter(tree) => hole_text(tree).hash(state),
+            Self::Actual(t) => t.hash(state),
+        }
+    }
+}
+
+impl<T: PartialOrd> PartialOrd for Interpolatable<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Actual(a), Self::Actual(b)) => a.partial_cmp(b),
+            (Self::Inter(a), Self::Inter(b)) => hole_text(a).partial_cmp(&hole_text(b)),
+            (Self::Inter(_), Self::Actual(_)) => Some(Ordering::Less),
+            (Self::Actual(_), Self::Inter(_)) => Some(Ordering::Greater),
+        }
+    }
+}
+
+impl<T: Ord> Ord for Interpolatable<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Actual(a), Self::Actual(b)) => a.cmp(b),
+            (Self::Inter(a), Self::Inter(b)) => hole_text(a).cmp(&hole_text(b)),
+            (Self::Inter(_), Self::Actual(_)) => Ordering::Less,
+            (Self::Actual(_), Self::Inter(_)) => Ordering::Greater,
+        }
+    }
+}
+
+/// Displays actual values as-is and holes as `${...}`
+impl<T: Display> Display for Interpolatable<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Actual(t) => t.fmt(f),
+            Self::Inter(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+                write!(f, "${{{}}}", group.stream())
+            }
+            Self::Inter(tree) => write!(f, "${{{tree}}}"),
+        }
+    }
+}
+
+impl<T: Default> Default for Interpolatable<T> {
+    fn default() -> Self {
+        Self::Actual(T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{hash_map::DefaultHasher, BTreeSet, HashSet},
+        hash::{Hash, Hasher},
+    };
+
+    use super::*;
+
+    fn hole(text: &str) -> Interpolatable<i32> {
+        Interpolatable::Inter(syn::parse_str(text).unwrap())
+    }
+
+    fn hash(value: &Interpolatable<i32>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn equal_holes_hash_equal() {
+        let (a, b) = (hole("{ a + 1 }"), hole("{a+1}"));
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        assert_ne!(a, hole("{ a + 2 }"));
+        assert_ne!(hole("{ a }"), Interpolatable::Actual(1));
+        assert_eq!(HashSet::from([a, b, Interpolatable::Actual(1)]).len(), 2);
+    }
+
+    #[test]
+    fn holes_order_first() {
+        let values = [Interpolatable::Actual(2), hole("{ b }"), Interpolatable::Actual(1), hole("{ a }")];
+        let sorted: Vec<_> = BTreeSet::from(values).into_iter().collect();
+        assert_eq!(sorted, [hole("{ a }"), hole("{ b }"), Interpolatable::Actual(1), Interpolatable::Actual(2)]);
+        for (a, b) in sorted.iter().zip(&sorted[1..]) {
+            assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+        }
+    }
+
+    #[test]
+    fn display_and_default() {
+        assert_eq!(hole("{ a + 1 }").to_string(), "${a + 1}");
+        assert_eq!(hole("x").to_string(), "${x}");
+        assert_eq!(Interpolatable::Actual(1).to_string(), "1");
+        assert_eq!(Interpolatable::<i32>::default(), Interpolatable::Actual(0));
+    }
+}
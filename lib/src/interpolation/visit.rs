@@ -0,0 +1,489 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet, LinkedList, VecDeque},
+    fmt::Debug,
+    ops::{Bound, ControlFlow, Range, RangeFrom, RangeInclusive, RangeTo, RangeToInclusive},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use proc_macro2::TokenTree;
+
+use crate::path::{FieldPath, PathSegment};
+
+use super::{Element, Interpolatable};
+
+/// Gives access to every hole of a value together with its location
+///
+/// Implemented for all std types that implement `Bake`, use `#[derive(VisitInterpolations)]`
+/// or `#[bake(visit)]` for your own types.
+pub trait VisitInterpolations {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree));
+
+    fn for_each_hole(&self, mut visitor: impl FnMut(&FieldPath, &TokenTree))
+    where
+        Self: Sized,
+    {
+        self.visit_interpolations(&mut FieldPath::new(), &mut visitor)
+    }
+
+    fn holes(&self) -> Vec<(FieldPath, TokenTree)>
+    where
+        Self: Sized,
+    {
+        let mut holes = Vec::new();
+        self.for_each_hole(|path, tree| holes.push((path.clone(), tree.clone())));
+        holes
+    }
+}
+
+/// Like [VisitInterpolations] but allows rewriting the holes
+///
+/// Sets and the keys of maps can not be changed in place and are not visited, unlike with [VisitInterpolations].
+pub trait VisitInterpolationsMut {
+    fn visit_interpolations_mut(
+        &mut self,
+        path: &mut FieldPath,
+        visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree),
+    );
+
+    fn for_each_hole_mut(&mut self, mut visitor: impl FnMut(&FieldPath, &mut TokenTree))
+    where
+        Self: Sized,
+    {
+        self.visit_interpolations_mut(&mut FieldPath::new(), &mut visitor)
+    }
+}
+
+impl<T: VisitInterpolations> VisitInterpolations for Interpolatable<T> {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+        match self {
+            Interpolatable::Inter(tree) => visitor(path, tree),
+            Interpolatable::Actual(t) => t.visit_interpolations(path, visitor),
+        }
+    }
+}
+
+impl<T: VisitInterpolationsMut> VisitInterpolationsMut for Interpolatable<T> {
+    fn visit_interpolations_mut(
+        &mut self,
+        path: &mut FieldPath,
+        visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree),
+    ) {
+        match self {
+            Interpolatable::Inter(tree) => visitor(path, tree),
+            Interpolatable::Actual(t) => t.visit_interpolations_mut(path, visitor),
+        }
+    }
+}
+
+/// The condition of a conditional element and optional elements are visited as holes
+impl<T: VisitInterpolations> VisitInterpolations for Element<T> {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+        match self {
+            Element::Present(t) => t.visit_interpolations(path, visitor),
+            Element::Optional(tree) => visitor(path, tree),
+            Element::Conditional(condition, t) => {
+                visitor(path, condition);
+                t.visit_interpolations(path, visitor)
+            }
+        }
+    }
+}
+
+impl<T: VisitInterpolationsMut> VisitInterpolationsMut for Element<T> {
+    fn visit_interpolations_mut(
+        &mut self,
+        path: &mut FieldPath,
+        visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree),
+    ) {
+        match self {
+            Element::Present(t) => t.visit_interpolations_mut(path, visitor),
+            Element::Optional(tree) => visitor(path, tree),
+            Element::Conditional(condition, t) => {
+                visitor(path, condition);
+                t.visit_interpolations_mut(path, visitor)
+            }
+        }
+    }
+}
+
+macro_rules! impl_leaves {
+    ($($T:ty)*) => {
+        $(impl VisitInterpolations for $T {
+            fn visit_interpolations(&self, _: &mut FieldPath, _: &mut dyn FnMut(&FieldPath, &TokenTree)) {}
+        }
+
+        impl VisitInterpolationsMut for $T {
+            fn visit_interpolations_mut(&mut self, _: &mut FieldPath, _: &mut dyn FnMut(&FieldPath, &mut TokenTree)) {}
+        })*
+    };
+}
+
+impl_leaves!(
+    () bool
+    u8 u16 u32 u64 u128 usize
+    i8 i16 i32 i64 i128 isize
+    f32 f64
+    char &str String Duration
+);
+
+macro_rules! impl_lists {
+    ($($T:ident),*) => {
+        $(impl<T: VisitInterpolations> VisitInterpolations for $T<T> {
+            fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+                for (index, element) in self.iter().enumerate() {
+                    path.with(PathSegment::Index(index), |path| element.visit_interpolations(path, visitor));
+                }
+            }
+        })*
+    };
+}
+
+impl_lists!(Vec, VecDeque, LinkedList, BTreeSet, HashSet, BinaryHeap);
+
+macro_rules! impl_lists_mut {
+    ($($T:ident),*) => {
+        $(impl<T: VisitInterpolationsMut> VisitInterpolationsMut for $T<T> {
+            fn visit_interpolations_mut(&mut self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree)) {
+                for (index, element) in self.iter_mut().enumerate() {
+                    path.with(PathSegment::Index(index), |path| element.visit_interpolations_mut(path, visitor));
+                }
+            }
+        })*
+    };
+}
+
+impl_lists_mut!(Vec, VecDeque, LinkedList);
+
+impl<T: VisitInterpolations> VisitInterpolations for [T] {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+        for (index, element) in self.iter().enumerate() {
+            path.with(PathSegment::Index(index), |path| element.visit_interpolations(path, visitor));
+        }
+    }
+}
+
+impl<T: VisitInterpolationsMut> VisitInterpolationsMut for [T] {
+    fn visit_interpolations_mut(
+        &mut self,
+        path: &mut FieldPath,
+        visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree),
+    ) {
+        for (index, element) in self.iter_mut().enumerate() {
+            path.with(PathSegment::Index(index), |path| element.visit_interpolations_mut(path, visitor));
+        }
+    }
+}
+
+impl<T: VisitInterpolations, const S: usize> VisitInterpolations for [T; S] {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+        self.as_slice().visit_interpolations(path, visitor)
+    }
+}
+
+impl<T: VisitInterpolationsMut, const S: usize> VisitInterpolationsMut for [T; S] {
+    fn visit_interpolations_mut(
+        &mut self,
+        path: &mut FieldPath,
+        visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree),
+    ) {
+        self.as_mut_slice().visit_interpolations_mut(path, visitor)
+    }
+}
+
+macro_rules! impl_maps {
+    ($($T:ident),*) => {
+        $(impl<K: Debug + VisitInterpolations, V: VisitInterpolations> VisitInterpolations for $T<K, V> {
+            /// Visits the key of every entry at `.key(..)` and its value at `[..]`
+            fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+                for (key, value) in self {
+                    path.with(PathSegment::MapKey(format!("{key:?}")), |path| key.visit_interpolations(path, visitor));
+                    path.with(PathSegment::Key(format!("{key:?}")), |path| value.visit_interpolations(path, visitor));
+                }
+            }
+        }
+
+        impl<K: Debug, V: VisitInterpolationsMut> VisitInterpolationsMut for $T<K, V> {
+            fn visit_interpolations_mut(&mut self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree)) {
+                for (key, value) in self {
+                    path.with(PathSegment::Key(format!("{key:?}")), |path| value.visit_interpolations_mut(path, visitor));
+                }
+            }
+        })*
+    };
+}
+
+impl_maps!(BTreeMap, HashMap);
+
+/// Types that contain at most one value, the path is not extended
+macro_rules! impl_wrappers {
+    ($($T:ident => $($variant:path)|*),*) => {
+        $(impl<T: VisitInterpolations> VisitInterpolations for $T<T> {
+            fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+                match self {
+                    $($variant(t))|* => t.visit_interpolations(path, visitor),
+                    #[allow(unreachable_patterns)]
+                    _ => {}
+                }
+            }
+        }
+
+        impl<T: VisitInterpolationsMut> VisitInterpolationsMut for $T<T> {
+            fn visit_interpolations_mut(&mut self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree)) {
+                match self {
+                    $($variant(t))|* => t.visit_interpolations_mut(path, visitor),
+                    #[allow(unreachable_patterns)]
+                    _ => {}
+                }
+            }
+        })*
+    };
+}
+
+impl_wrappers!(
+    Option => Some,
+    Bound => Bound::Included | Bound::Excluded
+);
+
+impl<T: VisitInterpolations + ?Sized> VisitInterpolations for Box<T> {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+        (**self).visit_interpolations(path, visitor)
+    }
+}
+
+impl<T: VisitInterpolationsMut + ?Sized> VisitInterpolationsMut for Box<T> {
+    fn visit_interpolations_mut(
+        &mut self,
+        path: &mut FieldPath,
+        visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree),
+    ) {
+        (**self).visit_interpolations_mut(path, visitor)
+    }
+}
+
+macro_rules! impl_shared {
+    ($($T:ident),*) => {
+        $(impl<T: VisitInterpolations + ?Sized> VisitInterpolations for $T<T> {
+            fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+                (**self).visit_interpolations(path, visitor)
+            }
+        })*
+    };
+}
+
+impl_shared!(Rc, Arc);
+
+impl<T: VisitInterpolations + Clone> VisitInterpolations for Cow<'_, T> {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+        self.as_ref().visit_interpolations(path, visitor)
+    }
+}
+
+impl<T: VisitInterpolations, E: VisitInterpolations> VisitInterpolations for Result<T, E> {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+        match self {
+            Ok(t) => t.visit_interpolations(path, visitor),
+            Err(e) => e.visit_interpolations(path, visitor),
+        }
+    }
+}
+
+impl<T: VisitInterpolationsMut, E: VisitInterpolationsMut> VisitInterpolationsMut for Result<T, E> {
+    fn visit_interpolations_mut(
+        &mut self,
+        path: &mut FieldPath,
+        visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree),
+    ) {
+        match self {
+            Ok(t) => t.visit_interpolations_mut(path, visitor),
+            Err(e) => e.visit_interpolations_mut(path, visitor),
+        }
+    }
+}
+
+impl<B: VisitInterpolations, C: VisitInterpolations> VisitInterpolations for ControlFlow<B, C> {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+        match self {
+            ControlFlow::Continue(c) => c.visit_interpolations(path, visitor),
+            ControlFlow::Break(b) => b.visit_interpolations(path, visitor),
+        }
+    }
+}
+
+impl<B: VisitInterpolationsMut, C: VisitInterpolationsMut> VisitInterpolationsMut for ControlFlow<B, C> {
+    fn visit_interpolations_mut(
+        &mut self,
+        path: &mut FieldPath,
+        visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree),
+    ) {
+        match self {
+            ControlFlow::Continue(c) => c.visit_interpolations_mut(path, visitor),
+            ControlFlow::Break(b) => b.visit_interpolations_mut(path, visitor),
+        }
+    }
+}
+
+macro_rules! impl_ranges {
+    ($($T:ident: $($field:ident)*),*) => {
+        $(impl<Idx: VisitInterpolations> VisitInterpolations for $T<Idx> {
+            fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+                $(path.with(PathSegment::Field(stringify!($field).to_owned()), |path| {
+                    self.$field.visit_interpolations(path, visitor)
+                });)*
+            }
+        }
+
+        impl<Idx: VisitInterpolationsMut> VisitInterpolationsMut for $T<Idx> {
+            fn visit_interpolations_mut(&mut self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree)) {
+                $(path.with(PathSegment::Field(stringify!($field).to_owned()), |path| {
+                    self.$field.visit_interpolations_mut(path, visitor)
+                });)*
+            }
+        })*
+    };
+}
+
+impl_ranges!(Range: start end, RangeFrom: start, RangeTo: end, RangeToInclusive: end);
+
+impl<Idx: VisitInterpolations> VisitInterpolations for RangeInclusive<Idx> {
+    fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+        path.with(PathSegment::Field("start".to_owned()), |path| self.start().visit_interpolations(path, visitor));
+        path.with(PathSegment::Field("end".to_owned()), |path| self.end().visit_interpolations(path, visitor));
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($($T:ident)*),*) => {
+        $(
+            #[allow(non_snake_case)]
+            impl<$($T: VisitInterpolations),*> VisitInterpolations for ($($T,)*) {
+                fn visit_interpolations(&self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &TokenTree)) {
+                    let ($($T,)*) = self;
+                    let mut index = 0;
+                    $(
+                        path.with(PathSegment::Field(index.to_string()), |path| $T.visit_interpolations(path, visitor));
+                        index += 1;
+                    )*
+                    let _ = index;
+                }
+            }
+
+            #[allow(non_snake_case)]
+            impl<$($T: VisitInterpolationsMut),*> VisitInterpolationsMut for ($($T,)*) {
+                fn visit_interpolations_mut(&mut self, path: &mut FieldPath, visitor: &mut dyn FnMut(&FieldPath, &mut TokenTree)) {
+                    let ($($T,)*) = self;
+                    let mut index = 0;
+                    $(
+                        path.with(PathSegment::Field(index.to_string()), |path| $T.visit_interpolations_mut(path, visitor));
+                        index += 1;
+                    )*
+                    let _ = index;
+                }
+            }
+        )*
+    };
+}
+
+impl_tuple!(
+    A,
+    A B,
+    A B C,
+    A B C D,
+    A B C D E,
+    A B C D E F,
+    A B C D E F G,
+    A B C D E F G H,
+    A B C D E F G H I,
+    A B C D E F G H I J,
+    A B C D E F G H I J K,
+    A B C D E F G H I J K L,
+    A B C D E F G H I J K L M,
+    A B C D E F G H I J K L M N,
+    A B C D E F G H I J K L M N O,
+    A B C D E F G H I J K L M N O P
+);
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+    use crate::{bake, Bake};
+
+    #[derive(Bake)]
+    #[bake(template = NodeTemplate, visit)]
+    pub enum Node {
+        #[interpolate]
+        Leaf(i64),
+        Branch {
+            #[interpolate(elements)]
+            children: Vec<Node>,
+            #[interpolate]
+            label: String,
+        },
+    }
+
+    fn hole<T>(name: &str) -> Interpolatable<T> {
+        Interpolatable::Inter(syn::parse_str(&format!("{{ {name} }}")).unwrap())
+    }
+
+    fn paths(holes: Vec<(FieldPath, TokenTree)>) -> Vec<String> {
+        holes.iter().map(|(path, tree)| format!("{path} = {tree}")).collect()
+    }
+
+    #[test]
+    fn derived_paths() {
+        let node = NodeTemplate::Branch {
+            children: vec![Interpolatable::Actual(Node::Leaf(1)), hole("b")],
+            label: hole("c"),
+        };
+        assert_eq!(
+            paths(node.holes()),
+            ["Branch.children[1] = { b }", "Branch.label = { c }"]
+        );
+        assert_eq!(paths(NodeTemplate::Leaf(hole("a")).holes()), ["Leaf.0 = { a }"]);
+        assert!(Node::Branch { children: vec![Node::Leaf(1)], label: "a".to_owned() }.holes().is_empty());
+    }
+
+    #[test]
+    fn elements() {
+        let elements = vec![
+            Element::Present(hole::<i64>("a")),
+            Element::Optional(parse_quote!({ b })),
+            Element::Conditional(parse_quote!({ c }), Interpolatable::Actual(1)),
+        ];
+        assert_eq!(paths(elements.holes()), ["[0] = { a }", "[1] = { b }", "[2] = { c }"]);
+    }
+
+    #[test]
+    fn map_keys() {
+        let key = hole::<String>("k");
+        let map = BTreeMap::from([(key.clone(), hole::<i64>("v"))]);
+        assert_eq!(paths(map.holes()), [format!("key({key:?}) = {{ k }}"), format!("[{key:?}] = {{ v }}")]);
+    }
+
+    #[test]
+    fn mut_skips_keys_and_sets() {
+        let mut map = BTreeMap::from([(hole::<String>("k"), hole::<i64>("v"))]);
+        let mut visited = Vec::new();
+        map.for_each_hole_mut(|_, tree| {
+            visited.push(tree.to_string());
+            *tree = parse_quote!({ w });
+        });
+        assert_eq!(visited, ["{ v }"]);
+        assert_eq!(map.holes().len(), 2);
+        assert_eq!(map.values().next().unwrap().to_string(), "${w}");
+
+        let set = BTreeSet::from([hole::<i64>("a")]);
+        assert_eq!(paths(set.holes()), ["[0] = { a }"]);
+    }
+
+    #[test]
+    fn rename_holes() {
+        let mut node = NodeTemplate::Branch { children: vec![hole("a")], label: hole("a") };
+        node.for_each_hole_mut(|path, tree| *tree = syn::parse_str(&format!("{{ {} }}", path.segments().len())).unwrap());
+        assert_eq!(paths(node.holes()), ["Branch.children[0] = { 3 }", "Branch.label = { 2 }"]);
+    }
+}
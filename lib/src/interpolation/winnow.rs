@@ -0,0 +1,97 @@
+//! winnow combinators for parsers that produce [Interpolatable]s
+
+use ::winnow::{
+    error::{FromExternalError, ModalError, ParserError},
+    Parser,
+};
+
+use super::{syntax::ScanError, HoleSyntax, Interpolatable, Interpolate, RuntimeInterpolationError};
+
+/// Parses a hole like `${...}`
+///
+/// The input is only advanced if the hole is well formed. Backtracks if the input does not start with a hole
+/// and cuts if the hole is never closed or does not contain a single token tree.
+pub fn interpolation<'a, T, E: ParserError<&'a str> + ModalError>(
+    input: &mut &'a str,
+) -> Result<Interpolatable<T>, E> {
+    interpolation_with(HoleSyntax::Dollar).parse_next(input)
+}
+
+/// Parses a hole written with `syntax`, see [interpolation]
+pub fn interpolation_with<'a, T, E: ParserError<&'a str> + ModalError>(
+    syntax: HoleSyntax,
+) -> impl FnMut(&mut &'a str) -> Result<Interpolatable<T>, E> {
+    move |input| match syntax.scan(input) {
+        Ok((rest, content)) => {
+            let hole = Interpolatable::parse_hole(content).map_err(|_| E::from_input(input).cut())?;
+            *input = rest;
+            Ok(hole)
+        }
+        Err(ScanError::NoHole) => Err(E::from_input(input)),
+        Err(_) => Err(E::from_input(input).cut()),
+    }
+}
+
+/// Accepts a hole like `${...}` or whatever `parser` accepts
+///
+/// `parser` is only tried if the input does not start with a hole, a malformed hole cuts.
+pub fn interpolatable<'a, T, E, P>(
+    mut parser: P,
+) -> impl FnMut(&mut &'a str) -> Result<Interpolatable<T>, E>
+where
+    E: ParserError<&'a str> + ModalError,
+    P: Parser<&'a str, T, E>,
+{
+    move |input| match HoleSyntax::Dollar.starts_hole(input) {
+        true => interpolation(input),
+        false => parser.parse_next(input).map(Interpolatable::Actual),
+    }
+}
+
+/// Fits the result of `parser`, a hole fails with the [RuntimeInterpolationError] at the start of the hole
+///
+/// Use this in parsers that run outside of macros and can not produce holes.
+pub fn fit_winnow<I: Clone, T, U, E, P>(mut parser: P) -> impl FnMut(&mut I) -> Result<T, E>
+where
+    U: Interpolate<T>,
+    E: FromExternalError<I, RuntimeInterpolationError>,
+    P: Parser<I, U, E>,
+{
+    move |input| {
+        let start = input.clone();
+        parser.parse_next(input)?.winnow(&start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::winnow::{ascii::dec_int, error::{ContextError, ErrMode}};
+
+    use super::*;
+
+    type Error = ErrMode<ContextError>;
+
+    fn number(input: &mut &str) -> Result<Interpolatable<i64>, Error> {
+        interpolatable(dec_int).parse_next(input)
+    }
+
+    #[test]
+    fn holes() {
+        let mut input = "${a} rest";
+        assert!(matches!(number(&mut input), Ok(Interpolatable::Inter(_))));
+        assert_eq!(input, " rest");
+
+        let mut input = "12";
+        assert!(matches!(number(&mut input), Ok(Interpolatable::Actual(12))));
+    }
+
+    #[test]
+    fn malformed_holes_cut() {
+        for text in ["${a", "${)}"] {
+            let mut input = text;
+            assert!(matches!(number(&mut input), Err(ErrMode::Cut(_))), "{text}");
+            assert_eq!(input, text);
+        }
+        assert!(matches!(number(&mut "a"), Err(ErrMode::Backtrack(_))));
+    }
+}
@@ -10,9 +10,23 @@ pub mod collections;
 pub mod pointers;
 pub mod time;
 pub mod functions;
+pub mod path;
+pub mod parser;
+pub mod diagnostics;
+pub mod baker;
+pub mod context;
+mod error;
+
+pub use baker::Baker;
+pub use context::BakeContext;
+pub use error::BakeError;
 
 pub use struct_baker_derive::*;
 
+// The derives refer to `struct_baker`, so the tests of this crate can use them
+#[cfg(test)]
+extern crate self as struct_baker;
+
 pub mod interpolation;
 
 pub mod util {
@@ -23,8 +37,39 @@ pub mod util {
 
 pub trait Bake {
     /// Return a TokenStream the produces an equivalent struct
+    ///
+    /// Implementations that can fail should return the error as a `compile_error!`, see [BakeError::to_compile_error].
     fn to_stream(&self) -> TokenStream;
 
+    /// Like [to_stream](Bake::to_stream) but returns an error instead of a `compile_error!` for values that can not be baked
+    ///
+    /// Containers report the errors of their elements with the path to the element.
+    fn try_to_stream(&self) -> Result<TokenStream, BakeError> {
+        Ok(self.to_stream())
+    }
+
+    /// Like [to_stream](Bake::to_stream) with the settings of `context`
+    fn to_stream_with(&self, context: &BakeContext) -> TokenStream {
+        let _ = context;
+        self.to_stream()
+    }
+
+    /// Like [try_to_stream](Bake::try_to_stream) with the settings of `context`
+    ///
+    /// Containers and derived types pass the context on to their elements and fields.
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let _ = context;
+        self.try_to_stream()
+    }
+
+    /// Bakes into the output of `baker` instead of tokens
+    ///
+    /// The default passes the tokens of [try_to_stream_with](Bake::try_to_stream_with) to [Baker::tokens].
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let tokens = self.try_to_stream_with(baker.context())?;
+        Ok(baker.tokens(tokens))
+    }
+
     fn to_token_tree(&self) -> TokenTree {
         let inner = self.to_stream();
         parse2(quote!({#inner})).expect("Wrapping a stream in brackets should always be a valid tree")
@@ -33,12 +78,49 @@ pub trait Bake {
 
 pub trait Bakeable {
     fn bake(&self) -> TokenStream;
+
+    fn bake_with(&self, context: &BakeContext) -> TokenStream {
+        let _ = context;
+        self.bake()
+    }
 }
 
-// Helper trait to avoid namespace conflicts in macros 
-// And circumvent incoherent impls of `Bake` on `Interpoaltabale`
+// Helper trait to avoid namespace conflicts in macros
+// And circumvent incoherent impls of `Bake` on types like `BakeableFnOnce` and `Interpolatable`
 impl<T: Bake> Bakeable for T {
     fn bake(&self) -> TokenStream {
         self.to_stream()
     }
+
+    fn bake_with(&self, context: &BakeContext) -> TokenStream {
+        self.to_stream_with(context)
+    }
+}
+
+pub trait TryBake {
+    fn try_bake(&self) -> Result<TokenStream, BakeError>;
+
+    fn try_bake_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError>;
+
+    /// Like [Bake::bake_into], used by the derive so fields of type `Interpolatable` work with every [Baker]
+    ///
+    /// The default passes the tokens of [try_bake_with](TryBake::try_bake_with) to [Baker::tokens].
+    fn try_bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let tokens = self.try_bake_with(baker.context())?;
+        Ok(baker.tokens(tokens))
+    }
+}
+
+impl<T: Bake + ?Sized> TryBake for T {
+    fn try_bake(&self) -> Result<TokenStream, BakeError> {
+        self.try_to_stream()
+    }
+
+    fn try_bake_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        self.try_to_stream_with(context)
+    }
+
+    fn try_bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        self.bake_into(baker)
+    }
 }
@@ -1,76 +1,177 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use crate::Bake;
+use crate::{baker::Fields, context::bake_with_context, path::PathSegment, Bake, BakeContext, BakeError, Baker};
 
 impl<B: Bake, C: Bake> Bake for std::ops::ControlFlow<B, C> {
-    fn to_stream(&self) -> TokenStream {
-        match self {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        Ok(match self {
             std::ops::ControlFlow::Continue(c) => {
-                let c = c.to_stream();
+                let c = c.try_to_stream_with(context)?;
                 quote!(std::ops::ControlFlow::Continue(#c))
             }
             std::ops::ControlFlow::Break(b) => {
-                let b = b.to_stream();
+                let b = b.try_to_stream_with(context)?;
                 quote!(std::ops::ControlFlow::Break(#b))
             }
-        }
+        })
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let (variant, inner) = match self {
+            std::ops::ControlFlow::Continue(c) => ("Continue", c.bake_into(baker)?),
+            std::ops::ControlFlow::Break(b) => ("Break", b.bake_into(baker)?),
+        };
+        Ok(baker.variant(quote!(std::ops::ControlFlow), variant, Fields::Unnamed(vec![inner])))
     }
 }
 
+/// Bakes a bound of a range, errors are marked with the name of the bound
+fn try_bound<Idx: Bake>(bound: &Idx, name: &str, context: &BakeContext) -> Result<TokenStream, BakeError> {
+    bound
+        .try_to_stream_with(context)
+        .map_err(|e| e.within(PathSegment::Field(name.to_owned())))
+}
+
+/// Like [try_bound] for a [Baker], returns the bound as a named field
+fn bake_bound<Idx: Bake, Bk: Baker>(bound: &Idx, name: &str, baker: &mut Bk) -> Result<(String, Bk::Output), BakeError> {
+    let bound = bound
+        .bake_into(baker)
+        .map_err(|e| e.within(PathSegment::Field(name.to_owned())))?;
+    Ok((name.to_owned(), bound))
+}
+
+// Ranges are baked as struct literals instead of `start..end`, so they keep their meaning
+// next to other operators and can be handed to a `Baker` as structs
+
 impl<Idx: Bake> Bake for std::ops::Range<Idx> {
-    fn to_stream(&self) -> TokenStream {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
         let std::ops::Range { start, end } = self;
-        let start = start.to_stream();
-        let end = end.to_stream();
-        quote!(#start .. #end)
+        let start = try_bound(start, "start", context)?;
+        let end = try_bound(end, "end", context)?;
+        Ok(quote!(std::ops::Range { start: #start, end: #end }))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let std::ops::Range { start, end } = self;
+        let fields = vec![bake_bound(start, "start", baker)?, bake_bound(end, "end", baker)?];
+        Ok(baker.structure(quote!(std::ops::Range), Fields::Named(fields)))
     }
 }
 
 impl<Idx: Bake> Bake for std::ops::RangeFrom<Idx> {
-    fn to_stream(&self) -> TokenStream {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
         let std::ops::RangeFrom { start } = self;
-        let start = start.to_stream();
-        quote!(#start..)
+        let start = try_bound(start, "start", context)?;
+        Ok(quote!(std::ops::RangeFrom { start: #start }))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let std::ops::RangeFrom { start } = self;
+        let fields = vec![bake_bound(start, "start", baker)?];
+        Ok(baker.structure(quote!(std::ops::RangeFrom), Fields::Named(fields)))
     }
 }
 
 impl<Idx: Bake> Bake for std::ops::RangeTo<Idx> {
-    fn to_stream(&self) -> TokenStream {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let std::ops::RangeTo { end } = self;
+        let end = try_bound(end, "end", context)?;
+        Ok(quote!(std::ops::RangeTo { end: #end }))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
         let std::ops::RangeTo { end } = self;
-        let end = end.to_stream();
-        quote!( .. #end)
+        let fields = vec![bake_bound(end, "end", baker)?];
+        Ok(baker.structure(quote!(std::ops::RangeTo), Fields::Named(fields)))
     }
 }
 
+/// The fields of `RangeInclusive` are private, so it is baked with `new`
 impl<Idx: Bake> Bake for std::ops::RangeInclusive<Idx> {
-    fn to_stream(&self) -> TokenStream {
-        let start = self.start().to_stream();
-        let end = self.end().to_stream();
-        quote!(#start ..= #end)
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let start = try_bound(self.start(), "start", context)?;
+        let end = try_bound(self.end(), "end", context)?;
+        Ok(quote!(std::ops::RangeInclusive::new(#start, #end)))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let (_, start) = bake_bound(self.start(), "start", baker)?;
+        let (_, end) = bake_bound(self.end(), "end", baker)?;
+        Ok(baker.structure(quote!(std::ops::RangeInclusive::new), Fields::Unnamed(vec![start, end])))
     }
 }
 
 impl<Idx: Bake> Bake for std::ops::RangeToInclusive<Idx> {
-    fn to_stream(&self) -> TokenStream {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let std::ops::RangeToInclusive { end } = self;
+        let end = try_bound(end, "end", context)?;
+        Ok(quote!(std::ops::RangeToInclusive { end: #end }))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
         let std::ops::RangeToInclusive { end } = self;
-        let end = end.to_stream();
-        quote!( .. #end)
+        let fields = vec![bake_bound(end, "end", baker)?];
+        Ok(baker.structure(quote!(std::ops::RangeToInclusive), Fields::Named(fields)))
     }
 }
 
 impl<T: Bake> Bake for std::ops::Bound<T> {
-    fn to_stream(&self) -> TokenStream {
-        match self {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        Ok(match self {
             Self::Included(t) => {
-                let t = t.to_stream();
+                let t = t.try_to_stream_with(context)?;
                 quote!(std::ops::Bound::Included(#t))
             }
             Self::Excluded(t) => {
-                let t = t.to_stream();
+                let t = t.try_to_stream_with(context)?;
                 quote!(std::ops::Bound::Excluded(#t))
             }
             Self::Unbounded => quote!(std::ops::Bound::Unbounded),
-        }
+        })
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let (variant, fields) = match self {
+            Self::Included(t) => ("Included", Fields::Unnamed(vec![t.bake_into(baker)?])),
+            Self::Excluded(t) => ("Excluded", Fields::Unnamed(vec![t.bake_into(baker)?])),
+            Self::Unbounded => ("Unbounded", Fields::Unit),
+        };
+        Ok(baker.variant(quote!(std::ops::Bound), variant, fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the value bakes into an expression and returns its tokens
+    fn expr(value: &impl Bake) -> String {
+        let tokens = value.to_stream();
+        syn::parse2::<syn::Expr>(tokens.clone()).unwrap();
+        tokens.to_string()
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(expr(&(1u8..2)), quote!(std::ops::Range { start: 1u8, end: 2u8 }).to_string());
+        assert_eq!(expr(&(1u8..=2)), quote!(std::ops::RangeInclusive::new(1u8, 2u8)).to_string());
+        assert_eq!(expr(&(..2u8)), quote!(std::ops::RangeTo { end: 2u8 }).to_string());
+        assert_eq!(expr(&(..=2u8)), quote!(std::ops::RangeToInclusive { end: 2u8 }).to_string());
+        assert_eq!(expr(&(1u8..)), quote!(std::ops::RangeFrom { start: 1u8 }).to_string());
     }
 }
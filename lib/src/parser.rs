@@ -0,0 +1,178 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use proc_macro2::TokenStream;
+
+use crate::{
+    diagnostics::Diagnostics,
+    interpolation::{Interpolatable, SourceMap},
+    Bake, BakeContext, TryBake,
+};
+
+/// Marks parsers of the shape `Fn(&str) -> Result<Interpolatable<T>, E>` that consume the whole input
+///
+/// `E` has to convert into a [ParseError], so the error can be reported at the token it occurred at.
+pub struct Complete;
+
+/// Marks parsers of the shape `Fn(&str) -> Result<(&str, Interpolatable<T>), E>` that return the remaining input
+///
+/// Like for [Complete] `E` has to convert into a [ParseError].
+pub struct Partial;
+
+/// An error of a [Complete] or [Partial] parser at a byte offset into the parsed text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(offset: usize, message: impl Display) -> Self {
+        Self {
+            offset,
+            message: message.to_string(),
+        }
+    }
+
+    /// An error at the start of `rest`, which has to be a suffix of `input` like the remaining input of a parser
+    pub fn at_rest(input: &str, rest: &str, message: impl Display) -> Self {
+        Self::new(input.len().saturating_sub(rest.len()), message)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Marks nom parsers of the shape `Fn(&str) -> IResult<&str, Interpolatable<T>>`
+#[cfg(feature = "nom")]
+pub struct Nom;
+
+/// A parser that can be turned into a macro with [parser_macro!](crate::parser_macro)
+///
+/// `Marker` only tells the implementations for the different parser shapes apart and is always inferred.
+pub trait MacroParser<T, Marker> {
+    /// Parses the text of `map` and reports errors at the token that caused them
+    fn parse_macro_input(&self, map: &SourceMap) -> syn::Result<Interpolatable<T>>;
+}
+
+impl<F, T, E> MacroParser<T, Complete> for F
+where
+    F: Fn(&str) -> Result<Interpolatable<T>, E>,
+    E: Into<ParseError>,
+{
+    fn parse_macro_input(&self, map: &SourceMap) -> syn::Result<Interpolatable<T>> {
+        self(map.text()).map_err(|e| error_at(map, e))
+    }
+}
+
+impl<F, T, E> MacroParser<T, Partial> for F
+where
+    F: for<'a> Fn(&'a str) -> Result<(&'a str, Interpolatable<T>), E>,
+    E: Into<ParseError>,
+{
+    fn parse_macro_input(&self, map: &SourceMap) -> syn::Result<Interpolatable<T>> {
+        let (rest, value) = self(map.text()).map_err(|e| error_at(map, e))?;
+        expect_end(map, rest)?;
+        Ok(value)
+    }
+}
+
+#[cfg(feature = "nom")]
+impl<F, T> MacroParser<T, Nom> for F
+where
+    F: for<'a> Fn(&'a str) -> nom::IResult<&'a str, Interpolatable<T>>,
+{
+    fn parse_macro_input(&self, map: &SourceMap) -> syn::Result<Interpolatable<T>> {
+        let (rest, value) = self(map.text()).map_err(|e| match e {
+            nom::Err::Incomplete(_) => map.error_at(map.text().len(), "Unexpected end of input"),
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                map.error_at_rest(e.input, format!("Syntax error: {}", e.code.description()))
+            }
+        })?;
+        expect_end(map, rest)?;
+        Ok(value)
+    }
+}
+
+/// Runs `parser` on the text of `input` and bakes the result, errors become `compile_error!`s
+///
+/// Everything the parser or the `Bake` impls report to [diagnostics](crate::diagnostics) is emitted as well.
+/// This is the body of the macros created by [parser_macro!](crate::parser_macro).
+///
+/// ```
+/// use struct_baker::{interpolation::Interpolatable, parser::{expand, ParseError}, quote};
+///
+/// fn parse_number(text: &str) -> Result<Interpolatable<u8>, ParseError> {
+///     let digits = text.trim_start();
+///     let offset = text.len() - digits.len();
+///     digits.parse().map(Interpolatable::Actual).map_err(|e| ParseError::new(offset, e))
+/// }
+///
+/// assert_eq!(expand(quote!(42), parse_number).to_string(), quote!({ 42u8 }).to_string());
+/// assert!(expand(quote!(x), parse_number).to_string().contains("compile_error"));
+/// ```
+pub fn expand<T: Bake, M>(input: TokenStream, parser: impl MacroParser<T, M>) -> TokenStream {
+    expand_with(input, parser, &BakeContext::default())
+}
+
+/// Like [expand] but bakes the result with the settings of `context`
+pub fn expand_with<T: Bake, M>(
+    input: TokenStream,
+    parser: impl MacroParser<T, M>,
+    context: &BakeContext,
+) -> TokenStream {
+    let map = SourceMap::from_tokens(input);
+    let (result, mut diagnostics) = Diagnostics::capture(|| {
+        let value = parser.parse_macro_input(&map)?;
+        value
+            .try_bake_with(context)
+            .map_err(|e| syn::Error::new(map.span_of(0..map.text().len()), e))
+    });
+    match result {
+        Ok(tokens) if !diagnostics.has_errors() => diagnostics.into_expr(Some(tokens)),
+        Ok(_) => diagnostics.into_expr(None),
+        Err(e) => {
+            diagnostics.push(e);
+            diagnostics.into_expr(None)
+        }
+    }
+}
+
+fn error_at(map: &SourceMap, error: impl Into<ParseError>) -> syn::Error {
+    let ParseError { offset, message } = error.into();
+    map.error_at(offset, message)
+}
+
+fn expect_end(map: &SourceMap, rest: &str) -> syn::Result<()> {
+    match rest.trim_start().is_empty() {
+        true => Ok(()),
+        false => Err(map.error_at_rest(rest.trim_start(), "Unexpected input")),
+    }
+}
+
+/// Defines a function-like proc macro that parses its input with a parser function and bakes the result
+///
+/// Has to be used in a proc-macro crate. The parser takes a `&str` and returns an [Interpolatable], either
+/// as `Result<Interpolatable<T>, E>`, as `Result<(&str, Interpolatable<T>), E>` with the remaining input
+/// or as a nom `IResult`. Errors are reported at the token they occurred at, remaining input is an error.
+///
+/// ```ignore
+/// struct_baker::parser_macro!(json, lib::parse_json_node);
+/// ```
+#[macro_export]
+macro_rules! parser_macro {
+    ($(#[$attr:meta])* $name:ident, $parser:path) => {
+        $(#[$attr])*
+        #[proc_macro]
+        pub fn $name(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+            $crate::parser::expand(input.into(), $parser).into()
+        }
+    };
+}
@@ -0,0 +1,84 @@
+use std::fmt::{self, Display, Formatter};
+
+/// A single step from a value to one of its parts
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// A named or positional field like `name` or `0`
+    Field(String),
+    /// The variant of an enum
+    Variant(String),
+    /// An element of a list
+    Index(usize),
+    /// The value of a map entry, the key is stored as its baked tokens or, by visitors, in its `Debug` representation
+    Key(String),
+    /// The key of a map entry itself
+    ///
+    /// Stored as `#` and the position of the entry when the key fails to bake, visitors use its `Debug` representation.
+    MapKey(String),
+}
+
+/// The location of a part of a value like `config.servers[3].tls`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FieldPath {
+    segments: Vec<PathSegment>,
+}
+
+impl FieldPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment)
+    }
+
+    /// Adds `segment` in front of all other segments
+    pub fn push_front(&mut self, segment: PathSegment) {
+        self.segments.insert(0, segment)
+    }
+
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Runs `f` with `segment` appended to the path
+    pub fn with<R>(&mut self, segment: PathSegment, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.push(segment);
+        let result = f(self);
+        self.pop();
+        result
+    }
+}
+
+impl FromIterator<PathSegment> for FieldPath {
+    fn from_iter<I: IntoIterator<Item = PathSegment>>(iter: I) -> Self {
+        Self {
+            segments: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Display for FieldPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Field(name) | PathSegment::Variant(name) if index == 0 => write!(f, "{name}")?,
+                PathSegment::Field(name) => write!(f, ".{name}")?,
+                PathSegment::Variant(name) => write!(f, "::{name}")?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+                PathSegment::Key(key) => write!(f, "[{key}]")?,
+                PathSegment::MapKey(key) if index == 0 => write!(f, "key({key})")?,
+                PathSegment::MapKey(key) => write!(f, ".key({key})")?,
+            }
+        }
+        Ok(())
+    }
+}
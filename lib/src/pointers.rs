@@ -1,78 +1,157 @@
-#[cfg(feature = "allow_pointers")]
 use std::ops::Deref;
 
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use crate::{Bake, Bakeable};
+use crate::{
+    baker::Fields, context::bake_with_context, Bake, BakeContext, BakeError, Baker,
+};
 
-const POINTER_WARNING: &str = "Smart pointers may not be baked corectly, try to implement the baking logic for this struct yourself or enable the `allow_pointers` feature if you know what you are doing";
+const POINTER_WARNING: &str = "Smart pointers may not be baked corectly, try to implement the baking logic for this struct yourself or enable the `allow_pointers` feature or `BakeContext::allow_pointers` if you know what you are doing";
 
 impl<T: Bake> Bake for std::rc::Rc<T> {
-    #[cfg(feature = "allow_pointers")]
-    fn bake(&self) -> TokenStream {
-        let inner = self.deref().bake();
-        quote!(std::rc::Rc::new(#inner))
+    bake_with_context!();
+
+    /// Fails unless the context allows pointers
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        if !context.pointers_allowed() {
+            return Err(BakeError::new(POINTER_WARNING));
+        }
+        let inner = self.deref().try_to_stream_with(context)?;
+        Ok(quote!(std::rc::Rc::new(#inner)))
     }
 
-    #[cfg(not(feature = "allow_pointers"))]
-    fn to_stream(&self) -> TokenStream {
-        panic!("{}", POINTER_WARNING)
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        if !baker.context().pointers_allowed() {
+            return Err(BakeError::new(POINTER_WARNING));
+        }
+        let inner = self.deref().bake_into(baker)?;
+        Ok(baker.structure(quote!(std::rc::Rc::new), Fields::Unnamed(vec![inner])))
     }
 }
 
 impl<T: Bake> Bake for std::sync::Arc<T> {
-    #[cfg(feature = "allow_pointers")]
-    fn bake(&self) -> TokenStream {
-        let inner = self.deref().bake();
-        quote!(std::sync::Arc::new(#inner))
+    bake_with_context!();
+
+    /// Fails unless the context allows pointers
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        if !context.pointers_allowed() {
+            return Err(BakeError::new(POINTER_WARNING));
+        }
+        let inner = self.deref().try_to_stream_with(context)?;
+        Ok(quote!(std::sync::Arc::new(#inner)))
     }
 
-    #[cfg(not(feature = "allow_pointers"))]
-    fn to_stream(&self) -> TokenStream {
-        panic!("{}", POINTER_WARNING)
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        if !baker.context().pointers_allowed() {
+            return Err(BakeError::new(POINTER_WARNING));
+        }
+        let inner = self.deref().bake_into(baker)?;
+        Ok(baker.structure(quote!(std::sync::Arc::new), Fields::Unnamed(vec![inner])))
     }
 }
 
 impl<T: Bake + Clone> Bake for std::borrow::Cow<'_, T> {
-    #[cfg(feature = "allow_pointers")]
-    fn bake(&self) -> TokenStream {
-        let inner = self.deref().bake();
-        quote!(std::borrow::Cow::new(#inner))
+    bake_with_context!();
+
+    /// Fails unless the context allows pointers
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        if !context.pointers_allowed() {
+            return Err(BakeError::new(POINTER_WARNING));
+        }
+        let inner = self.deref().try_to_stream_with(context)?;
+        Ok(quote!(std::borrow::Cow::Owned(#inner)))
     }
 
-    #[cfg(not(feature = "allow_pointers"))]
-    fn to_stream(&self) -> TokenStream {
-        panic!("{}", POINTER_WARNING)
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        if !baker.context().pointers_allowed() {
+            return Err(BakeError::new(POINTER_WARNING));
+        }
+        let inner = self.deref().bake_into(baker)?;
+        Ok(baker.structure(quote!(std::borrow::Cow::Owned), Fields::Unnamed(vec![inner])))
     }
 }
 
 impl<B: Bake + Copy> Bake for std::cell::Cell<B> {
-    fn to_stream(&self) -> TokenStream {
-        let inner = self.get().bake();
-        quote!(std::cell::Cell::new(#inner))
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let inner = self.get().try_to_stream_with(context)?;
+        Ok(quote!(std::cell::Cell::new(#inner)))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let inner = self.get().bake_into(baker)?;
+        Ok(baker.structure(quote!(std::cell::Cell::new), Fields::Unnamed(vec![inner])))
     }
 }
 
 impl<B: Bake> Bake for std::cell::RefCell<B> {
-    fn to_stream(&self) -> TokenStream {
-        let inner = self.borrow().bake();
-        quote!(std::cell::Cell::new(#inner))
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        let inner = self.borrow().try_to_stream_with(context)?;
+        Ok(quote!(std::cell::RefCell::new(#inner)))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let inner = self.borrow().bake_into(baker)?;
+        Ok(baker.structure(quote!(std::cell::RefCell::new), Fields::Unnamed(vec![inner])))
     }
 }
 
 impl<B: Bake> Bake for std::cell::OnceCell<B> {
-    fn to_stream(&self) -> TokenStream {
-        match self.get() {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+        Ok(match self.get() {
             Some(inner) => {
-                let inner = inner.bake();
-                quote! {
-                    let cell = std::cell::OnceCell::new();
-                    cell.set(#inner);
-                    cell
-                }
+                let inner = inner.try_to_stream_with(context)?;
+                quote!(std::cell::OnceCell::from(#inner))
             }
             None => quote!(std::cell::OnceCell::new()),
-        }
+        })
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        Ok(match self.get() {
+            Some(inner) => {
+                let inner = inner.bake_into(baker)?;
+                baker.structure(quote!(std::cell::OnceCell::from), Fields::Unnamed(vec![inner]))
+            }
+            None => baker.structure(quote!(std::cell::OnceCell::new), Fields::Unnamed(vec![])),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, OnceCell, RefCell};
+
+    use super::*;
+
+    /// Checks that the value bakes into an expression and returns its tokens
+    fn expr(value: &impl Bake) -> String {
+        let tokens = value.to_stream();
+        syn::parse2::<syn::Expr>(tokens.clone()).unwrap();
+        tokens.to_string()
+    }
+
+    #[test]
+    fn cells() {
+        assert_eq!(expr(&Cell::new(1u8)), quote!(std::cell::Cell::new(1u8)).to_string());
+        assert_eq!(expr(&RefCell::new(1u8)), quote!(std::cell::RefCell::new(1u8)).to_string());
+        assert_eq!(expr(&OnceCell::<u8>::new()), quote!(std::cell::OnceCell::new()).to_string());
+        let once = OnceCell::new();
+        once.set(1u8).unwrap();
+        assert_eq!(expr(&once), quote!(std::cell::OnceCell::from(1u8)).to_string());
+    }
+
+    #[test]
+    fn pointers() {
+        let context = BakeContext::new().allow_pointers(false);
+        assert!(std::rc::Rc::new(1).try_to_stream_with(&context).is_err());
+        let baked = std::rc::Rc::new(1u8).try_to_stream_with(&context.allow_pointers(true)).unwrap();
+        assert_eq!(baked.to_string(), "std :: rc :: Rc :: new (1u8)");
     }
 }
@@ -1,14 +1,22 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use crate::Bake;
-
+use crate::{baker::Fields, context::bake_with_context, Bake, BakeContext, BakeError, Baker};
 
+/// Only consists of integers, which do not depend on the context, so it never fails
 impl Bake for std::time::Duration {
-    fn to_stream(&self) -> TokenStream {
+    bake_with_context!();
+
+    fn try_to_stream_with(&self, _: &BakeContext) -> Result<TokenStream, BakeError> {
         let secs = self.as_secs().to_stream();
         let nanos = self.subsec_nanos().to_stream();
 
-        quote!(std::time::Duration::new(#secs, #nanos))
+        Ok(quote!(std::time::Duration::new(#secs, #nanos)))
+    }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        let secs = self.as_secs().bake_into(baker)?;
+        let nanos = self.subsec_nanos().bake_into(baker)?;
+        Ok(baker.structure(quote!(std::time::Duration::new), Fields::Unnamed(vec![secs, nanos])))
     }
 }
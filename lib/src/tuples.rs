@@ -1,25 +1,46 @@
 use proc_macro2::TokenStream;
 use quote::quote;
 
-use crate::Bake;
+use crate::{context::bake_with_context, path::PathSegment, Bake, BakeContext, BakeError, Baker};
 
 impl Bake for () {
     fn to_stream(&self) -> TokenStream {
         quote!(())
     }
+
+    fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
+        Ok(baker.tuple(Vec::new()))
+    }
 }
 
 macro_rules! impl_tuple {
     ($($($T:ident)+),*) => {
         $(
             impl<$($T: Bake),*> Bake for ($($T,)*) {
+                bake_with_context!();
+
+                #[allow(non_snake_case)]
+                fn try_to_stream_with(&self, context: &BakeContext) -> Result<TokenStream, BakeError> {
+                    let ($($T,)*) = self;
+                    let mut index = 0;
+                    $(
+                        let $T = $T.try_to_stream_with(context).map_err(|e| e.within(PathSegment::Field(index.to_string())))?;
+                        index += 1;
+                    )*
+                    let _ = index;
+                    Ok(quote!(($(#$T,)*)))
+                }
+
                 #[allow(non_snake_case)]
-                fn to_stream(&self) -> TokenStream {
+                fn bake_into<Bk: Baker>(&self, baker: &mut Bk) -> Result<Bk::Output, BakeError> {
                     let ($($T,)*) = self;
+                    let mut index = 0;
                     $(
-                        let $T = $T.to_stream();
+                        let $T = $T.bake_into(baker).map_err(|e| e.within(PathSegment::Field(index.to_string())))?;
+                        index += 1;
                     )*
-                    quote!(($(#$T,)*))
+                    let _ = index;
+                    Ok(baker.tuple(vec![$($T),*]))
                 }
             }
         )*
@@ -36,5 +57,11 @@ impl_tuple!(
     A B C D E F G,
     A B C D E F G H,
     A B C D E F G H I,
-    A B C D E F G H I J
+    A B C D E F G H I J,
+    A B C D E F G H I J K,
+    A B C D E F G H I J K L,
+    A B C D E F G H I J K L M,
+    A B C D E F G H I J K L M N,
+    A B C D E F G H I J K L M N O,
+    A B C D E F G H I J K L M N O P
 );